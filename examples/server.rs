@@ -0,0 +1,130 @@
+extern crate subway;
+
+use std::fmt::Write as FmtWrite;
+use std::io::{self, BufRead, Write};
+
+use subway::dijkstra::Dijkstra;
+use subway::graph::{Graph, GraphEdge, GraphVertex};
+use subway::weight::FloatWeight;
+
+type Router<'a> = Dijkstra<
+    'a,
+    GraphVertex<'a, String, FloatWeight>,
+    GraphEdge<'a, String, FloatWeight>,
+    FloatWeight,
+>;
+
+struct Request {
+    from: String,
+    to: String,
+}
+
+enum Response {
+    Route { stations: Vec<String>, minutes: f64 },
+    UnknownStation(String),
+    NoRoute,
+}
+
+fn parse(line: &str) -> Option<Request> {
+    let mut words = line.split_whitespace();
+    let from = words.next()?.to_owned();
+    let to = words.next()?.to_owned();
+    Some(Request { from, to })
+}
+
+fn handle<'a>(
+    graph: &'a Graph<'a, String, FloatWeight>,
+    router: &Router<'a>,
+    request: &Request,
+) -> Response {
+    let from = match graph.vertex(&request.from) {
+        Some(from) => from,
+        None => return Response::UnknownStation(request.from.clone()),
+    };
+    let to = match graph.vertex(&request.to) {
+        Some(to) => to,
+        None => return Response::UnknownStation(request.to.clone()),
+    };
+
+    match router.shortest_path(from, to) {
+        Some((route, weight)) => Response::Route {
+            stations: route.iter().map(|v| v.id().clone()).collect(),
+            minutes: weight.value(),
+        },
+        None => Response::NoRoute,
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn to_json(response: &Response) -> String {
+    let mut json = String::new();
+    match response {
+        Response::Route { stations, minutes } => {
+            let stations: Vec<_> = stations.iter().map(|s| json_string(s)).collect();
+            write!(
+                json,
+                r#"{{"route":[{}],"minutes":{}}}"#,
+                stations.join(","),
+                minutes
+            )
+            .unwrap();
+        }
+        Response::UnknownStation(id) => {
+            write!(
+                json,
+                r#"{{"error":"unknown station","station":{}}}"#,
+                json_string(id)
+            )
+            .unwrap();
+        }
+        Response::NoRoute => json.push_str(r#"{"error":"no route"}"#),
+    }
+    json
+}
+
+fn minutes(value: f64) -> FloatWeight {
+    FloatWeight::new(value).unwrap()
+}
+
+fn main() {
+    let lines = vec![
+        ("Seoul", "Cityhall", 2.0),
+        ("Cityhall", "Seoul", 2.0),
+        ("Cityhall", "Jonggak", 1.5),
+        ("Jonggak", "Cityhall", 1.5),
+        ("Seoul", "Hoehyeon", 1.0),
+        ("Hoehyeon", "Myeongdong", 1.5),
+    ];
+    let graph = Graph::from_edges(
+        lines
+            .into_iter()
+            .map(|(from, to, weight)| (from.to_owned(), to.to_owned(), minutes(weight))),
+    );
+    let router = graph.dijkstra();
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = line.expect("failed to read a request");
+        let request = match parse(&line) {
+            Some(request) => request,
+            None => continue,
+        };
+        let response = handle(&graph, &router, &request);
+        writeln!(stdout, "{}", to_json(&response)).expect("failed to write a response");
+    }
+}
@@ -0,0 +1,232 @@
+#[cfg(feature = "no_std")]
+use alloc::vec::Vec;
+use collections::{DefaultIndexHasher, IndexHasher, Key, Map};
+use core::mem;
+
+use dijkstra::{Dijkstra, Edge, Route, Vertex, Weight};
+
+type QueryKey = (usize, usize);
+
+/// Router that remembers the routes of the last `capacity` distinct `(start, end)`
+/// queries, forgetting the least recently asked first. Changing the graph through
+/// `rebuild` or `set_hop_tiebreak` forgets every route.
+pub struct CachedDijkstra<'a, V, E, W, S = DefaultIndexHasher>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+{
+    dijkstra: Dijkstra<'a, V, E, W, S>,
+    routes: LruMap<QueryKey, Option<Route<'a, V, W>>>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<'a, V, E, W, S> CachedDijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    pub fn new(dijkstra: Dijkstra<'a, V, E, W, S>, capacity: usize) -> Self {
+        CachedDijkstra {
+            dijkstra,
+            routes: LruMap::new(capacity),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn dijkstra(&self) -> &Dijkstra<'a, V, E, W, S> {
+        &self.dijkstra
+    }
+
+    /// Like `Dijkstra::shortest_path`, answered from the cache when the same query was
+    /// asked recently. Vertices outside the graph are answered with `None` uncached.
+    pub fn shortest_path(&mut self, start: &'a V, end: &'a V) -> Option<Route<'a, V, W>> {
+        let key = (self.dijkstra.index_of(start)?, self.dijkstra.index_of(end)?);
+        if let Some(route) = self.routes.get(&key) {
+            self.hits += 1;
+            return route.clone();
+        }
+
+        self.misses += 1;
+        let route = self.dijkstra.shortest_path(start, end);
+        self.routes.insert(key, route.clone());
+        route
+    }
+
+    /// Number of queries answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of queries that had to be searched.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.len() == 0
+    }
+
+    /// Keeps at most `capacity` routes from now on, forgetting the least recently asked
+    /// ones beyond it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.routes.set_capacity(capacity);
+    }
+
+    pub fn clear(&mut self) {
+        self.routes.clear();
+    }
+
+    /// Like `Dijkstra::rebuild`, and forgets every route.
+    pub fn rebuild(&mut self, list: impl IntoIterator<Item = &'a V>) {
+        self.dijkstra.rebuild(list);
+        self.clear();
+    }
+
+    /// Like `Dijkstra::set_hop_tiebreak`, and forgets every route.
+    pub fn set_hop_tiebreak(&mut self, enabled: bool) {
+        self.dijkstra.set_hop_tiebreak(enabled);
+        self.clear();
+    }
+}
+
+// Map that forgets its least recently used entry beyond `capacity`. Entries live in a slab
+// linked from most to least recently used, so a hit only relinks one entry.
+struct LruMap<K, T> {
+    capacity: usize,
+    slots: Map<K, usize>,
+    entries: Vec<LruEntry<K, T>>,
+    newest: Option<usize>,
+    oldest: Option<usize>,
+}
+
+struct LruEntry<K, T> {
+    key: K,
+    value: T,
+    newer: Option<usize>,
+    older: Option<usize>,
+}
+
+impl<K, T> LruMap<K, T>
+where
+    K: Key + Clone,
+{
+    fn new(capacity: usize) -> Self {
+        LruMap {
+            capacity,
+            slots: Map::new(),
+            entries: vec![],
+            newest: None,
+            oldest: None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn get(&mut self, key: &K) -> Option<&T> {
+        let slot = *self.slots.get(key)?;
+        self.unlink(slot);
+        self.push_newest(slot);
+        Some(&self.entries[slot].value)
+    }
+
+    fn insert(&mut self, key: K, value: T) {
+        if let Some(&slot) = self.slots.get(&key) {
+            self.entries[slot].value = value;
+            self.unlink(slot);
+            self.push_newest(slot);
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            // Reuse the slot of the entry forgotten.
+            let slot = self.oldest.expect("full cache has an oldest entry");
+            self.unlink(slot);
+            let old_key = mem::replace(&mut self.entries[slot].key, key.clone());
+            self.slots.remove(&old_key);
+            self.entries[slot].value = value;
+            self.slots.insert(key, slot);
+            self.push_newest(slot);
+            return;
+        }
+        let slot = self.entries.len();
+        self.entries.push(LruEntry {
+            key: key.clone(),
+            value,
+            newer: None,
+            older: None,
+        });
+        self.slots.insert(key, slot);
+        self.push_newest(slot);
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            let slot = self.oldest.expect("nonempty cache has an oldest entry");
+            self.remove(slot);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.slots.clear();
+        self.entries.clear();
+        self.newest = None;
+        self.oldest = None;
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let (newer, older) = (self.entries[slot].newer, self.entries[slot].older);
+        match newer {
+            Some(newer) => self.entries[newer].older = older,
+            None => self.newest = older,
+        }
+        match older {
+            Some(older) => self.entries[older].newer = newer,
+            None => self.oldest = newer,
+        }
+    }
+
+    fn push_newest(&mut self, slot: usize) {
+        self.entries[slot].newer = None;
+        self.entries[slot].older = self.newest;
+        match self.newest {
+            Some(newest) => self.entries[newest].newer = Some(slot),
+            None => self.oldest = Some(slot),
+        }
+        self.newest = Some(slot);
+    }
+
+    // Moves the last entry into the freed slot, so that the slots stay contiguous.
+    fn remove(&mut self, slot: usize) {
+        self.unlink(slot);
+        let removed = self.entries.swap_remove(slot);
+        self.slots.remove(&removed.key);
+        if slot == self.entries.len() {
+            return;
+        }
+        let (newer, older) = (self.entries[slot].newer, self.entries[slot].older);
+        match newer {
+            Some(newer) => self.entries[newer].older = Some(slot),
+            None => self.newest = Some(slot),
+        }
+        match older {
+            Some(older) => self.entries[older].newer = Some(slot),
+            None => self.oldest = Some(slot),
+        }
+        let key = self.entries[slot].key.clone();
+        self.slots.insert(key, slot);
+    }
+}
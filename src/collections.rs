@@ -0,0 +1,153 @@
+#[cfg(feature = "no_std")]
+use alloc::vec::Vec;
+#[cfg(not(feature = "no_std"))]
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "no_std")]
+use core::marker::PhantomData;
+
+#[cfg(not(feature = "no_std"))]
+pub use std::collections::{BinaryHeap, HashMap as Map, HashSet as Set, VecDeque};
+
+#[cfg(feature = "no_std")]
+pub use alloc::collections::{BTreeMap as Map, BTreeSet as Set, BinaryHeap, VecDeque};
+
+/// What a type needs to be used as a `Map` key or `Set` element: `Eq + Hash` by
+/// default, `Ord` under `no_std` where the maps are B-trees.
+#[cfg(not(feature = "no_std"))]
+pub trait Key: Eq + Hash {}
+
+#[cfg(not(feature = "no_std"))]
+impl<T> Key for T where T: Eq + Hash + ?Sized {}
+
+#[cfg(feature = "no_std")]
+pub trait Key: Ord {}
+
+#[cfg(feature = "no_std")]
+impl<T> Key for T where T: Ord + ?Sized {}
+
+/// Hasher builder for the vertex index of a `Dijkstra`. Any `BuildHasher` by default;
+/// under `no_std` the index is a B-tree and the hasher is unused.
+#[cfg(not(feature = "no_std"))]
+pub trait IndexHasher: BuildHasher {}
+
+#[cfg(not(feature = "no_std"))]
+impl<T> IndexHasher for T where T: BuildHasher {}
+
+#[cfg(feature = "no_std")]
+pub trait IndexHasher {}
+
+#[cfg(feature = "no_std")]
+impl<T> IndexHasher for T {}
+
+#[cfg(not(feature = "no_std"))]
+pub use std::collections::hash_map::RandomState as DefaultIndexHasher;
+
+#[cfg(feature = "no_std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultIndexHasher {
+    _private: (),
+}
+
+/// Map from keys to indices, hashed with `S`. It can also be searched by anything the keys
+/// borrow as, through `find`.
+#[derive(Clone)]
+pub(crate) struct IndexMap<K, S> {
+    #[cfg(not(feature = "no_std"))]
+    map: Map<K, usize, S>,
+    // Indices by the hash of their key, which `Borrow` keeps equal to that of the borrowed
+    // form.
+    #[cfg(not(feature = "no_std"))]
+    by_hash: Map<u64, Vec<usize>>,
+    #[cfg(feature = "no_std")]
+    map: Map<K, usize>,
+    // Keys with their indices in key order, which `Borrow` keeps the same for the borrowed
+    // form.
+    #[cfg(feature = "no_std")]
+    sorted: Vec<(K, usize)>,
+    #[cfg(feature = "no_std")]
+    _hasher: PhantomData<S>,
+}
+
+impl<K, S> IndexMap<K, S>
+where
+    K: Key + Clone,
+    S: IndexHasher,
+{
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn with_hasher(hasher: S) -> Self {
+        IndexMap {
+            map: Map::with_hasher(hasher),
+            by_hash: Map::new(),
+        }
+    }
+
+    #[cfg(feature = "no_std")]
+    pub(crate) fn with_hasher(_: S) -> Self {
+        IndexMap {
+            map: Map::new(),
+            sorted: vec![],
+            _hasher: PhantomData,
+        }
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<usize> {
+        self.map.get(key).copied()
+    }
+
+    pub(crate) fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn insert(&mut self, key: K, index: usize) {
+        let hash = self.map.hasher().hash_one(&key);
+        self.by_hash.entry(hash).or_default().push(index);
+        self.map.insert(key, index);
+    }
+
+    #[cfg(feature = "no_std")]
+    pub(crate) fn insert(&mut self, key: K, index: usize) {
+        let position = self.sorted.partition_point(|(sorted, _)| *sorted <= key);
+        self.sorted.insert(position, (key.clone(), index));
+        self.map.insert(key, index);
+    }
+
+    /// Index of the key that borrows as `query`, where `borrowed(index)` is the borrowed
+    /// form of the key at `index`.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn find<'q, Q, F>(&self, query: &Q, borrowed: F) -> Option<usize>
+    where
+        Q: Key + ?Sized + 'q,
+        F: Fn(usize) -> &'q Q,
+    {
+        let hash = self.map.hasher().hash_one(query);
+        let indices = self.by_hash.get(&hash)?;
+        indices
+            .iter()
+            .copied()
+            .find(|&index| borrowed(index) == query)
+    }
+
+    /// Index of the key that borrows as `query`, where `borrowed(index)` is the borrowed
+    /// form of the key at `index`.
+    #[cfg(feature = "no_std")]
+    pub(crate) fn find<'q, Q, F>(&self, query: &Q, borrowed: F) -> Option<usize>
+    where
+        Q: Key + ?Sized + 'q,
+        F: Fn(usize) -> &'q Q,
+    {
+        let position = self
+            .sorted
+            .binary_search_by(|&(_, index)| borrowed(index).cmp(query))
+            .ok()?;
+        Some(self.sorted[position].1)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.map.clear();
+        #[cfg(not(feature = "no_std"))]
+        self.by_hash.clear();
+        #[cfg(feature = "no_std")]
+        self.sorted.clear();
+    }
+}
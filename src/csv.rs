@@ -0,0 +1,47 @@
+#[cfg(feature = "no_std")]
+use alloc::string::String;
+#[cfg(feature = "no_std")]
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Write};
+
+/// Writes `matrix` as CSV, where `matrix[i][j]` is the distance from `labels[i]` to
+/// `labels[j]` and `None` stands for unreachable. The first row and column hold the
+/// labels, and an unreachable distance is an empty cell. Labels are quoted when they
+/// contain a comma, a quote or a line break.
+pub fn matrix_to_csv<L, T>(
+    matrix: &[Vec<Option<T>>],
+    labels: &[L],
+    writer: &mut impl Write,
+) -> fmt::Result
+where
+    L: Display,
+    T: Display,
+{
+    for label in labels {
+        writer.write_char(',')?;
+        write_field(writer, label)?;
+    }
+    writer.write_str("\n")?;
+
+    for (label, row) in labels.iter().zip(matrix) {
+        write_field(writer, label)?;
+        for distance in row {
+            writer.write_char(',')?;
+            if let Some(distance) = distance {
+                write_field(writer, distance)?;
+            }
+        }
+        writer.write_str("\n")?;
+    }
+    Ok(())
+}
+
+fn write_field(writer: &mut impl Write, value: impl Display) -> fmt::Result {
+    let mut field = String::new();
+    write!(field, "{}", value)?;
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(writer, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        writer.write_str(&field)
+    }
+}
@@ -1,17 +1,82 @@
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap, HashSet};
-use std::hash::Hash;
-use std::iter::{self, FromIterator};
-use std::marker::{PhantomData, Sized};
+#[cfg(feature = "no_std")]
+use alloc::string::String;
+#[cfg(feature = "no_std")]
+use alloc::vec::Vec;
+use collections::{BinaryHeap, DefaultIndexHasher, IndexHasher, IndexMap, Key, Map, Set};
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::error;
+use core::fmt;
+use core::fmt::Write;
+use core::iter;
+use core::marker::{PhantomData, Sized};
+use core::ptr;
+
+use graph::Graph;
 
 pub trait Weight
 where
-    Self: Clone + Ord,
+    Self: Clone + PartialOrd,
 {
     fn add(&self, other: &Self) -> Self;
     fn zero() -> Self;
+    /// The unit weight, such as the weight of a single hop. Adding it to `zero` gives it
+    /// back, and adding it `n` times to `zero` stands for `n` units.
+    fn one() -> Self;
     fn infinity() -> Self;
     fn is_infinity(&self) -> bool;
+
+    /// Whether this is the largest finite weight, which a saturating `add` can reach
+    /// without being exact. Budget checks treat such a weight as beyond any budget. By
+    /// default only infinity counts, for weights whose sums saturate to it.
+    fn is_max(&self) -> bool {
+        self.is_infinity()
+    }
+
+    /// This weight added up `factor` times, zero for a factor of zero. An infinite weight
+    /// stays infinite, and the default adds repeatedly, so integer weights and floats
+    /// override it with a multiplication.
+    fn scale(&self, factor: u32) -> Self {
+        if self.is_infinity() {
+            return Self::infinity();
+        }
+        (0..factor).fold(Self::zero(), |sum, _| sum.add(self))
+    }
+
+    /// Total order the search uses for weights. The default is `partial_cmp`, which is
+    /// already total for weights that are `Ord`; weights with incomparable values, such as
+    /// `f64` with `NaN`, must override it. Every search compares weights only through
+    /// this, so the default panics on a weight that is not equal to itself in every build.
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        assert!(
+            Self::eq(self, self) && Self::eq(other, other),
+            "non-total weight ordering: a weight is not equal to itself"
+        );
+        self.partial_cmp(other)
+            .expect("non-total weight ordering: weights are incomparable")
+    }
+}
+
+pub trait BucketWeight
+where
+    Self: Weight,
+{
+    /// Index of the bucket of width `bucket_size` this weight falls into, so a weight in
+    /// `[i * bucket_size, (i + 1) * bucket_size)` returns `i`. `None` when `bucket_size`
+    /// is not positive, and `usize::MAX` when the index is too large for a `usize`.
+    fn bucket(&self, bucket_size: &Self) -> Option<usize>;
+}
+
+/// Weight with a wider type to add up routes in, so that sums which would saturate `Self`
+/// stay exact, such as `u128` for `u64`.
+pub trait WideWeight
+where
+    Self: Weight,
+{
+    type Wide: Weight;
+    fn widen(&self) -> Self::Wide;
+    /// `wide` as `Self`, or infinity when it does not fit.
+    fn narrow(wide: &Self::Wide) -> Self;
 }
 
 pub trait Edge<'a, V, W>
@@ -22,33 +87,193 @@ where
 {
     fn get_to(&'a self) -> &'a V;
     fn get_weight(&self) -> &W;
+
+    /// Stable id of this edge, for edges that carry their own. By default `None`, and the
+    /// edge is identified by its endpoints and its position among the parallel edges
+    /// between them, as described on `EdgeId`.
+    fn id(&self) -> Option<EdgeId> {
+        None
+    }
+}
+
+/// Cost accumulated along a route out of its edge weights, such as a sum together with the
+/// heaviest edge so far, for `Dijkstra::find_shortest_path_accumulated`. Routes are
+/// compared by `key` rather than by the accumulated value itself.
+///
+/// The search is only exact when `key` is monotone: taking an edge never lowers it, and
+/// of two accumulations with `a.key() <= b.key()`, `a.combine(w).key() <= b.combine(w).key()`
+/// for every edge weight `w`.
+pub trait Accumulator<W>
+where
+    Self: Clone,
+{
+    type Key: Ord;
+    fn combine(&self, edge_weight: &W) -> Self;
+    fn key(&self) -> Self::Key;
+}
+
+/// Edge that may stand for a chain of two other edges, such as a shortcut added by
+/// contracting the vertex between them.
+pub trait Shortcut
+where
+    Self: Sized,
+{
+    fn shortcut_of(&self) -> Option<(&Self, &Self)>;
+}
+
+/// Expands `edge` recursively into the original, non-shortcut edges it stands for, in
+/// path order.
+pub fn unpack_shortcut<E>(edge: &E) -> Vec<&E>
+where
+    E: Shortcut,
+{
+    let mut edges = vec![];
+
+    let mut stack = vec![edge];
+    while let Some(edge) = stack.pop() {
+        match edge.shortcut_of() {
+            Some((first, second)) => {
+                stack.push(second);
+                stack.push(first);
+            }
+            None => edges.push(edge),
+        }
+    }
+    edges
+}
+
+/// Edge that belongs to a transit line, for routing with transfer penalties.
+pub trait LineEdge<'a, V, W>
+where
+    Self: Edge<'a, V, W>,
+    V: Vertex<'a, Self, W> + 'a,
+    W: Weight,
+{
+    type Line: Eq;
+    fn line(&self) -> &Self::Line;
+}
+
+pub trait ModeEdge<'a, V, W>
+where
+    Self: Edge<'a, V, W>,
+    V: Vertex<'a, Self, W> + 'a,
+    W: Weight,
+{
+    type Mode: Eq;
+    fn mode(&self) -> &Self::Mode;
+}
+
+/// Edge that may run on a timetable, for routing where weights are times of day.
+pub trait ScheduledEdge<'a, V, W>
+where
+    Self: Edge<'a, V, W>,
+    V: Vertex<'a, Self, W> + 'a,
+    W: Weight,
+{
+    /// Time the edge departs at, after which it arrives its weight later. `None` for an
+    /// edge that can be taken at any time, such as a walk.
+    fn departure(&self) -> Option<&W>;
+}
+
+/// Edge of a line that runs at a frequency rather than on a timetable.
+pub trait HeadwayEdge<'a, V, W>
+where
+    Self: LineEdge<'a, V, W>,
+    V: Vertex<'a, Self, W> + 'a,
+    W: Weight,
+{
+    /// Average time between two departures of the line. `None` for an edge that can be
+    /// taken without waiting, such as a walk.
+    fn headway(&self) -> Option<&W>;
 }
 
 pub trait Vertex<'a, E, W>
 where
-    Self: Sized + Eq + Hash + 'a,
+    Self: Sized + Key + 'a,
     E: Edge<'a, Self, W> + 'a,
     W: Weight,
 {
     type Edges: IntoIterator<Item = &'a E>;
     fn edges(&'a self) -> Self::Edges;
+
+    /// Edges arriving at this vertex, each with the vertex it leaves from. By default the
+    /// edges of every vertex in `graph` are scanned; vertices that store their back-edges
+    /// can return those instead.
+    fn incoming_edges(&'a self, graph: &[&'a Self]) -> Vec<(&'a Self, &'a E)> {
+        graph
+            .iter()
+            .flat_map(|&from| {
+                from.edges()
+                    .into_iter()
+                    .filter(move |edge| edge.get_to() == self)
+                    .map(move |edge| (from, edge))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Timeout,
+    NoPath,
+    CorruptedBacktrack,
+    NegativeEdgeEncountered { from: usize, to: usize },
+    MalformedMatrix,
+    LimitExceeded,
+    EmptyGraph,
+    InvalidBucketSize,
+    CapacityOverflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Timeout => write!(f, "search exceeded its iteration limit"),
+            Error::NoPath => write!(f, "no path between starts and ends"),
+            Error::CorruptedBacktrack => write!(f, "backtracker has no valid predecessor chain"),
+            Error::NegativeEdgeEncountered { from, to } => {
+                write!(f, "negative edge from vertex #{} to vertex #{}", from, to)
+            }
+            Error::MalformedMatrix => write!(f, "adjacency matrix is not square over the labels"),
+            Error::LimitExceeded => write!(f, "search settled more vertices than allowed"),
+            Error::EmptyGraph => write!(f, "graph has no vertices"),
+            Error::InvalidBucketSize => write!(f, "bucket size is not positive"),
+            Error::CapacityOverflow => write!(f, "result needs more memory than available"),
+        }
+    }
 }
 
-#[derive(PartialEq, Eq)]
-struct UnvisitedVertex<W>
+impl error::Error for Error {}
+
+/// Entry of a search frontier: the vertex at `index`, reached at `weight` through `hops`
+/// edges. Entries are ordered in reverse, so a `BinaryHeap` pops the lightest first and,
+/// among equally light ones, the one with the fewest hops.
+pub struct UnvisitedVertex<W>
 where
     W: Weight,
 {
-    index: usize,
-    weight: W,
+    pub index: usize,
+    pub weight: W,
+    pub hops: usize,
+}
+
+impl<W> PartialEq for UnvisitedVertex<W>
+where
+    W: Weight,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
 }
 
+impl<W> Eq for UnvisitedVertex<W> where W: Weight {}
+
 impl<W> PartialOrd for UnvisitedVertex<W>
 where
     W: Weight,
 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(other.weight.cmp(&self.weight))
+        Some(self.cmp(other))
     }
 }
 
@@ -57,116 +282,3247 @@ where
     W: Weight,
 {
     fn cmp(&self, other: &Self) -> Ordering {
-        other.weight.cmp(&self.weight)
+        other
+            .weight
+            .total_cmp(&self.weight)
+            .then_with(|| other.hops.cmp(&self.hops))
+    }
+}
+
+// Searches that keep a heap of `UnvisitedVertex` push through here, so that a weight which
+// is not equal to itself is caught by `Weight::total_cmp` even before another one is
+// compared with it.
+fn push_unvisited<W>(
+    unvisiteds: &mut BinaryHeap<UnvisitedVertex<W>>,
+    index: usize,
+    weight: W,
+    hops: usize,
+) where
+    W: Weight,
+{
+    assert!(
+        weight.total_cmp(&weight).is_eq(),
+        "non-total weight ordering: a weight is not equal to itself"
+    );
+    unvisiteds.push(UnvisitedVertex {
+        index,
+        weight,
+        hops,
+    });
+}
+
+/// Binary min-heap of vertex indices that keeps no weights of its own. Each index is
+/// stored at most once, and an index pushed again is moved up in place, so `less` must
+/// only ever order an index earlier than before while it is in the heap.
+struct IndexedHeap {
+    heap: Vec<usize>,
+    positions: Vec<Option<usize>>,
+}
+
+impl IndexedHeap {
+    fn new(len: usize) -> Self {
+        IndexedHeap {
+            heap: vec![],
+            positions: iter::repeat_n(None, len).collect(),
+        }
+    }
+
+    fn push<F>(&mut self, index: usize, less: F)
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        let position = match self.positions[index] {
+            Some(position) => position,
+            None => {
+                self.heap.push(index);
+                self.positions[index] = Some(self.heap.len() - 1);
+                self.heap.len() - 1
+            }
+        };
+        self.sift_up(position, &less);
+    }
+
+    fn pop<F>(&mut self, less: F) -> Option<usize>
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let top = self.heap.swap_remove(0);
+        self.positions[top] = None;
+        if !self.heap.is_empty() {
+            self.positions[self.heap[0]] = Some(0);
+            self.sift_down(0, &less);
+        }
+        Some(top)
+    }
+
+    fn sift_up<F>(&mut self, mut position: usize, less: &F)
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if !less(self.heap[position], self.heap[parent]) {
+                break;
+            }
+            self.swap(position, parent);
+            position = parent;
+        }
+    }
+
+    fn sift_down<F>(&mut self, mut position: usize, less: &F)
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        loop {
+            let mut smallest = position;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.heap.len() && less(self.heap[child], self.heap[smallest]) {
+                    smallest = child;
+                }
+            }
+            if smallest == position {
+                break;
+            }
+            self.swap(position, smallest);
+            position = smallest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a]] = Some(a);
+        self.positions[self.heap[b]] = Some(b);
+    }
+}
+
+// Penalized searches run for each alternative route before giving up on finding another.
+const ALTERNATIVE_ATTEMPTS: usize = 4;
+
+/// Identifies an edge by the indices of its endpoints and its position among the parallel
+/// edges between them, in the order `Vertex::edges` yields them, unless the edge gives its
+/// own through `Edge::id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeId {
+    pub from: usize,
+    pub to: usize,
+    pub index: usize,
+}
+
+/// Vertex of the line graph built by `Dijkstra::to_line_graph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LineNode {
+    /// Boarding at the vertex of this index, before riding any edge.
+    Departure(usize),
+    /// Having ridden this edge.
+    Edge(EdgeId),
+    /// Leaving at the vertex of this index, after riding an edge there.
+    Arrival(usize),
+}
+
+/// Constraints for `Dijkstra::search`. Every field left at its default imposes nothing,
+/// and any combination may be set at once.
+pub struct SearchOptions<'o, 'a, V, W>
+where
+    V: 'a,
+{
+    /// Only these vertices may be visited.
+    pub allowed: Option<&'o Set<&'a V>>,
+    /// These vertices may not be visited.
+    pub avoid_vertices: Option<&'o Set<&'a V>>,
+    /// These edges may not be taken.
+    pub avoid_edges: Option<&'o Set<EdgeId>>,
+    /// Edges weighing the given weight instead of their own.
+    pub overrides: Option<&'o Map<EdgeId, W>>,
+    /// No edge heavier than this may be taken.
+    pub max_edge_weight: Option<W>,
+    /// No route heavier than this is returned.
+    pub budget: Option<W>,
+    /// No route with more edges than this is returned.
+    pub max_hops: Option<usize>,
+    /// Cost of passing through a vertex on top of the edge weights.
+    pub vertex_cost: Option<&'o dyn Fn(&'a V) -> W>,
+    /// Whether `vertex_cost` is charged for the start vertex.
+    pub include_start_cost: bool,
+    /// Fails with `Error::Timeout` after settling this many vertices.
+    pub max_iterations: Option<usize>,
+}
+
+impl<'o, 'a, V, W> Default for SearchOptions<'o, 'a, V, W> {
+    fn default() -> Self {
+        SearchOptions {
+            allowed: None,
+            avoid_vertices: None,
+            avoid_edges: None,
+            overrides: None,
+            max_edge_weight: None,
+            budget: None,
+            max_hops: None,
+            vertex_cost: None,
+            include_start_cost: false,
+            max_iterations: None,
+        }
+    }
+}
+
+/// A found route with its weight. Paths are ordered by weight and then by number of
+/// edges, so two paths with the same weight and length compare equal even if they go
+/// through different vertices.
+#[derive(Debug, Clone)]
+pub struct ShortestPath<'a, V, W>
+where
+    V: 'a,
+{
+    pub route: Vec<&'a V>,
+    pub weight: W,
+}
+
+impl<'a, V, W> ShortestPath<'a, V, W> {
+    pub fn hops(&self) -> usize {
+        self.route.len().saturating_sub(1)
+    }
+}
+
+impl<'a, V, W> From<(Vec<&'a V>, W)> for ShortestPath<'a, V, W> {
+    fn from((route, weight): (Vec<&'a V>, W)) -> Self {
+        ShortestPath { route, weight }
+    }
+}
+
+impl<'a, V, W> PartialEq for ShortestPath<'a, V, W>
+where
+    W: Weight,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, V, W> Eq for ShortestPath<'a, V, W> where W: Weight {}
+
+impl<'a, V, W> PartialOrd for ShortestPath<'a, V, W>
+where
+    W: Weight,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, V, W> Ord for ShortestPath<'a, V, W>
+where
+    W: Weight,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.weight
+            .total_cmp(&other.weight)
+            .then_with(|| self.hops().cmp(&other.hops()))
+    }
+}
+
+/// State of a search in progress, which can be resumed toward new ends with
+/// `Dijkstra::resume_search`. It belongs to the `Dijkstra` that began it.
+pub struct Search<W>
+where
+    W: Weight,
+{
+    weights: Vec<W>,
+    backtracker: Vec<Option<usize>>,
+    hops: Vec<usize>,
+    visiteds: Vec<bool>,
+    // What the heap orders by instead of `weights` when not empty, as in A*.
+    estimates: Vec<W>,
+    unvisiteds: IndexedHeap,
+    iterations: usize,
+    trace: Option<Vec<HeapOperation<usize>>>,
+}
+
+/// An operation on the heap of a search, as recorded by `Dijkstra::trace_search`. A push
+/// of a vertex already in the heap moves it up after its distance improved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapOperation<T> {
+    Push(T),
+    Pop(T),
+}
+
+impl<W> Search<W>
+where
+    W: Weight,
+{
+    // Search over `len` states, none of them reached yet. Every search runs on this, with
+    // vertex indices or numbered states of its own, such as (vertex, hops) pairs.
+    fn new(len: usize) -> Self {
+        Search {
+            weights: iter::repeat_n(W::infinity(), len).collect(),
+            backtracker: iter::repeat_n(None, len).collect(),
+            hops: iter::repeat_n(0, len).collect(),
+            visiteds: iter::repeat_n(false, len).collect(),
+            estimates: vec![],
+            unvisiteds: IndexedHeap::new(len),
+            iterations: 0,
+            trace: None,
+        }
+    }
+
+    // Adds a state not reached yet and returns its index, for searches that number their
+    // states as they discover them.
+    fn add_state(&mut self) -> usize {
+        self.weights.push(W::infinity());
+        self.backtracker.push(None);
+        self.hops.push(0);
+        self.visiteds.push(false);
+        if !self.estimates.is_empty() {
+            self.estimates.push(W::infinity());
+        }
+        self.unvisiteds.positions.push(None);
+        self.weights.len() - 1
+    }
+
+    fn start(&mut self, index: usize, weight: W) {
+        self.weights[index] = weight;
+        self.backtracker[index] = Some(index);
+        self.push(index);
+    }
+
+    // Reaches `to` from `from` at `weight` through `hops` edges, when that is lighter, or
+    // as light with fewer hops, than `to` was reached before. Returns whether it was.
+    fn reach(&mut self, from: usize, to: usize, weight: W, hops: usize) -> bool {
+        if self.visiteds[to] {
+            return false;
+        }
+        let is_shorter = match self.weights[to].total_cmp(&weight) {
+            Ordering::Greater => true,
+            Ordering::Equal => hops < self.hops[to],
+            Ordering::Less => false,
+        };
+        if is_shorter {
+            self.weights[to] = weight;
+            self.backtracker[to] = Some(from);
+            self.hops[to] = hops;
+            self.push(to);
+        }
+        is_shorter
+    }
+
+    // Pops the lightest state reached and settles it.
+    fn settle(&mut self) -> Option<usize> {
+        let now = self.pop()?;
+        self.iterations += 1;
+        self.visiteds[now] = true;
+        Some(now)
+    }
+
+    // The heap orders states by their current entries in `weights`, or `estimates`, and
+    // `hops`.
+    fn push(&mut self, index: usize) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(HeapOperation::Push(index));
+        }
+        let keys = if self.estimates.is_empty() {
+            &self.weights
+        } else {
+            &self.estimates
+        };
+        let hops = &self.hops;
+        self.unvisiteds.push(index, |a, b| {
+            keys[a]
+                .total_cmp(&keys[b])
+                .then(hops[a].cmp(&hops[b]))
+                .is_lt()
+        });
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        let keys = if self.estimates.is_empty() {
+            &self.weights
+        } else {
+            &self.estimates
+        };
+        let hops = &self.hops;
+        let index = self.unvisiteds.pop(|a, b| {
+            keys[a]
+                .total_cmp(&keys[b])
+                .then(hops[a].cmp(&hops[b]))
+                .is_lt()
+        })?;
+        if let Some(trace) = &mut self.trace {
+            trace.push(HeapOperation::Pop(index));
+        }
+        Some(index)
     }
 }
 
-pub struct Dijkstra<'a, V, E, W>
+/// Completed single-source search that is kept up to date as edge weights decrease with
+/// `Dijkstra::decrease_edge_weight`. It belongs to the `Dijkstra` that built it.
+pub struct SourceTree<W>
+where
+    W: Weight,
+{
+    search: Search<W>,
+    decreased: Map<(usize, usize), W>,
+}
+
+/// How a vertex differs between two source trees, as found by `Dijkstra::tree_diff`. Each
+/// pair holds the value in the first tree and then in the second.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change<'a, V, W> {
+    /// Distance from the root, `None` where the vertex is unreachable.
+    pub distance: (Option<W>, Option<W>),
+    /// Vertex the route from the root arrives from, `None` at the root or where the
+    /// vertex is unreachable.
+    pub predecessor: (Option<&'a V>, Option<&'a V>),
+}
+
+/// A route with its weight, as returned by most searches.
+pub type Route<'a, V, W> = (Vec<&'a V>, W);
+
+/// Routes there and back between two vertices, each `None` when it does not exist.
+pub type RoundTrip<'a, V, W> = (Option<Route<'a, V, W>>, Option<Route<'a, V, W>>);
+
+/// One step of a route: the vertex it leaves, the edge taken and the vertex it reaches.
+pub type Segment<'a, V, E> = (&'a V, &'a E, &'a V);
+
+/// Consecutive vertices of a route travelled in one mode, each run with its mode.
+pub type ModeSegments<'a, V, M> = Vec<(&'a M, Vec<&'a V>)>;
+
+pub type MultimodalRoute<'a, V, M, W> = (Vec<&'a V>, W, ModeSegments<'a, V, M>);
+
+type EdgeRoute<'a, V, E, W> = (Vec<&'a V>, Vec<&'a E>, W);
+
+pub struct Dijkstra<'a, V, E, W, S = DefaultIndexHasher>
 where
     V: Vertex<'a, E, W> + 'a,
     E: Edge<'a, V, W> + 'a,
     W: Weight,
 {
     graph: Vec<&'a V>,
-    v_to_index_map: HashMap<&'a V, usize>,
+    v_to_index_map: IndexMap<&'a V, S>,
+    hop_tiebreak: bool,
     _marker: PhantomData<(E, W)>,
 }
 
-impl<'a, V, E, W> Dijkstra<'a, V, E, W>
+// Derived `Clone` would also require `E: Clone` and `W: Clone`, which are only marker types.
+impl<'a, V, E, W, S> Clone for Dijkstra<'a, V, E, W, S>
 where
     V: Vertex<'a, E, W> + 'a,
     E: Edge<'a, V, W> + 'a,
     W: Weight,
+    S: Clone,
 {
-    pub fn new(list: impl IntoIterator<Item = &'a V>) -> Self {
-        let graph: Vec<_> = list.into_iter().collect();
-        let v_to_index_map = graph.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+    fn clone(&self) -> Self {
         Dijkstra {
-            graph,
-            v_to_index_map,
+            graph: self.graph.clone(),
+            v_to_index_map: self.v_to_index_map.clone(),
+            hop_tiebreak: self.hop_tiebreak,
             _marker: PhantomData,
         }
     }
+}
 
-    pub fn find_shorted_path(
-        &self,
-        starts: impl IntoIterator<Item = &'a V>,
-        ends: impl IntoIterator<Item = &'a V>,
-    ) -> (Vec<&'a V>, W) {
-        let mut weights: Vec<_> = iter::repeat(W::infinity()).take(self.graph.len()).collect();
-        let mut unvisiteds = BinaryHeap::<UnvisitedVertex<W>>::new();
-
-        let start_set = HashSet::<usize>::from_iter(
-            starts
-                .into_iter()
-                .filter_map(|v| self.v_to_index_map.get(v))
-                .map(|&i| i),
-        );
-        let end_set = HashSet::<usize>::from_iter(
-            ends.into_iter()
-                .filter_map(|v| self.v_to_index_map.get(v))
-                .map(|&i| i),
-        );
-
-        for &i in start_set.iter() {
-            weights[i] = W::zero();
-            unvisiteds.push(UnvisitedVertex {
-                index: i,
-                weight: weights[i].clone(),
-            });
-        }
-
-        let mut backtracker: Vec<_> = iter::repeat(0).take(self.graph.len()).collect();
-        let mut visiteds: Vec<_> = iter::repeat(false).take(self.graph.len()).collect();
+/// Lazily settles the vertices reachable from the starts, yielding each with its distance
+/// in nondecreasing order of distance. Created by `Dijkstra::settle_order`.
+pub struct SettleOrder<'d, 'a, V, E, W, S = DefaultIndexHasher>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+{
+    dijkstra: &'d Dijkstra<'a, V, E, W, S>,
+    search: Search<W>,
+}
 
-        let start_pair = unvisiteds.pop().unwrap();
+impl<'d, 'a, V, E, W, S> Iterator for SettleOrder<'d, 'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    type Item = (&'a V, W);
 
-        let mut now = start_pair.index;
-        let mut weight_sum = start_pair.weight;
-        while !end_set.contains(&now) {
-            let now_vertex = self.graph[now];
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.dijkstra.settle_next(&mut self.search)?;
+        Some((self.dijkstra.graph[now], self.search.weights[now].clone()))
+    }
+}
 
-            for edge in now_vertex.edges() {
-                let to_vertex = edge.get_to();
-                let to = *self.v_to_index_map.get(&to_vertex).unwrap();
+/// Routes from a start to an end, each differing enough from those before it, found by
+/// the penalty method of `Dijkstra::alternatives`. Created by `Dijkstra::alternatives_iter`.
+pub struct AlternativesIterator<'d, 'a, V, E, W, S = DefaultIndexHasher>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+{
+    dijkstra: &'d Dijkstra<'a, V, E, W, S>,
+    start_set: Set<usize>,
+    end_set: Set<usize>,
+    overlap_threshold: f64,
+    penalties: Map<(usize, usize), usize>,
+    routes_edges: Vec<Set<(usize, usize)>>,
+}
 
-                if visiteds[to] {
-                    continue;
-                }
+impl<'d, 'a, V, E, W, S> Iterator for AlternativesIterator<'d, 'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    type Item = (Vec<&'a V>, W);
 
-                let weight = edge.get_weight();
+    fn next(&mut self) -> Option<Self::Item> {
+        let dijkstra = self.dijkstra;
+        for _ in 0..ALTERNATIVE_ATTEMPTS {
+            let penalties = &self.penalties;
+            let (route, _) = dijkstra
+                .search_path_by(
+                    &self.start_set,
+                    &self.end_set,
+                    None,
+                    |from, edge, weight_sum| {
+                        let to = dijkstra.index(edge.get_to());
+                        let weight = edge.get_weight();
+                        let penalty = penalties.get(&(from, to)).copied().unwrap_or(0);
+                        Some(weight_sum.add(&weight.scale(penalty as u32 + 1)))
+                    },
+                )
+                .ok()?;
 
-                let added_weight = weight_sum.add(weight);
+            let route_edges: Vec<_> = route
+                .windows(2)
+                .map(|pair| (dijkstra.index(pair[0]), dijkstra.index(pair[1])))
+                .collect();
 
-                if weights[to] > added_weight {
-                    weights[to] = added_weight.clone();
-                    backtracker[to] = now;
-                }
+            let is_distinct = self.routes_edges.iter().all(|accepted_edges| {
+                let shared = route_edges
+                    .iter()
+                    .filter(|&edge| accepted_edges.contains(edge))
+                    .count();
+                let overlap = if route_edges.is_empty() {
+                    1.0
+                } else {
+                    shared as f64 / route_edges.len() as f64
+                };
+                overlap < self.overlap_threshold
+            });
 
-                unvisiteds.push(UnvisitedVertex {
-                    index: to,
-                    weight: weights[to].clone(),
-                })
+            for &edge in &route_edges {
+                *self.penalties.entry(edge).or_insert(0) += 1;
             }
-            visiteds[now] = true;
 
-            let mut next_index = now;
-            while visiteds[next_index] {
-                let pair = unvisiteds.pop().unwrap();
-                next_index = pair.index;
-                weight_sum = pair.weight;
+            if self.routes_edges.is_empty() || is_distinct {
+                self.routes_edges.push(route_edges.into_iter().collect());
+                let weight = dijkstra.route_weight(&route);
+                return Some((route, weight));
             }
-            now = next_index;
         }
+        None
+    }
+}
 
-        let mut route = vec![];
+impl<'a, V, E, W> Dijkstra<'a, V, E, W>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+{
+    pub fn new(list: impl IntoIterator<Item = &'a V>) -> Self {
+        Dijkstra::with_hasher(list, DefaultIndexHasher::default())
+    }
 
-        while !start_set.contains(&now) {
-            route.insert(0, self.graph[now]);
-            now = backtracker[now];
+    /// Like `new`, but fails with `Error::EmptyGraph` when `list` has no vertices, so a
+    /// `Dijkstra` built this way always has at least one.
+    pub fn try_new(list: impl IntoIterator<Item = &'a V>) -> Result<Self, Error> {
+        let dijkstra = Dijkstra::new(list);
+        if dijkstra.graph.is_empty() {
+            Err(Error::EmptyGraph)
+        } else {
+            Ok(dijkstra)
         }
-        route.insert(0, self.graph[now]);
-
-        (route, weight_sum)
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    /// Like `new`, but hashes the vertex index with `hasher`.
+    pub fn with_hasher(list: impl IntoIterator<Item = &'a V>, hasher: S) -> Self {
+        let mut dijkstra = Dijkstra {
+            graph: vec![],
+            v_to_index_map: IndexMap::with_hasher(hasher),
+            hop_tiebreak: true,
+            _marker: PhantomData,
+        };
+        dijkstra.rebuild(list);
+        dijkstra
+    }
+
+    /// Replaces the vertex list in place, keeping the existing allocations. A vertex
+    /// listed more than once is kept at its first position only.
+    pub fn rebuild(&mut self, list: impl IntoIterator<Item = &'a V>) {
+        self.graph.clear();
+        self.v_to_index_map.clear();
+        for v in list {
+            if !self.v_to_index_map.contains_key(&v) {
+                self.v_to_index_map.insert(v, self.graph.len());
+                self.graph.push(v);
+            }
+        }
+        self.debug_assert_indices();
+    }
+
+    /// Among routes of equal weight, searches prefer the one with fewer edges. This is on
+    /// by default; when disabled, whichever equal-weight route is found first wins.
+    pub fn set_hop_tiebreak(&mut self, enabled: bool) {
+        self.hop_tiebreak = enabled;
+    }
+
+    pub fn index_of(&self, v: &V) -> Option<usize> {
+        let index = self.v_to_index_map.get(&v);
+        debug_assert!(
+            index.is_none_or(|index| index < self.graph.len()),
+            "vertex index is out of bounds of the vertex list"
+        );
+        index
+    }
+
+    pub fn vertex(&self, index: usize) -> Option<&'a V> {
+        self.graph.get(index).copied()
+    }
+
+    /// Stored vertex that borrows as `key`, looked up through the vertex index.
+    pub fn vertex_by_key<K>(&self, key: &K) -> Option<&'a V>
+    where
+        V: Borrow<K>,
+        K: Key + ?Sized,
+    {
+        let graph = &self.graph;
+        let index = self
+            .v_to_index_map
+            .find(key, |index| graph[index].borrow())?;
+        Some(graph[index])
+    }
+
+    pub fn shortest_path_by_key<K>(&self, start: &K, end: &K) -> Option<(Vec<&'a V>, W)>
+    where
+        V: Borrow<K>,
+        K: Key + ?Sized,
+    {
+        self.shortest_path(self.vertex_by_key(start)?, self.vertex_by_key(end)?)
+    }
+
+    pub fn vertices(&self) -> &[&'a V] {
+        &self.graph
+    }
+
+    pub fn into_vertices(self) -> Vec<&'a V> {
+        self.graph
+    }
+
+    pub fn edges_between(&self, a: &V, b: &V) -> Vec<&'a E> {
+        match self.index_of(a) {
+            Some(a) => self.graph[a]
+                .edges()
+                .into_iter()
+                .filter(|edge| edge.get_to() == b)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// A vertex that is both a start and an end is reached at zero weight, so it is
+    /// returned on its own. See `find_shortest_path_with_trivial_ends` to avoid that.
+    pub fn find_shorted_path(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> (Vec<&'a V>, W) {
+        self.search_path(starts, ends, None)
+            .expect("no path between starts and ends")
+    }
+
+    pub fn find_shortest_path_limited(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        max_iterations: usize,
+    ) -> Result<(Vec<&'a V>, W), Error> {
+        let options = SearchOptions {
+            max_iterations: Some(max_iterations),
+            ..SearchOptions::default()
+        };
+        self.search(starts, ends, &options)
+    }
+
+    /// Like `find_shortest_path_limited`, which also counts vertices settled, but the bound
+    /// is optional and exceeding it fails with `Error::LimitExceeded`.
+    pub fn find_shortest_path_settling(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        max_settled: Option<usize>,
+    ) -> Result<(Vec<&'a V>, W), Error> {
+        let options = SearchOptions {
+            max_iterations: max_settled,
+            ..SearchOptions::default()
+        };
+        self.search(starts, ends, &options)
+            .map_err(|error| match error {
+                Error::Timeout => Error::LimitExceeded,
+                error => error,
+            })
+    }
+
+    /// Like `find_shorted_path`, but when `allow_trivial` is false an end that is also a
+    /// start only counts once it is reached through at least one edge, either from another
+    /// start or by a cycle back to itself.
+    pub fn find_shortest_path_with_trivial_ends(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        allow_trivial: bool,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+        if allow_trivial {
+            return self
+                .search_path_by(&start_set, &end_set, None, Self::relax_edge)
+                .ok();
+        }
+
+        let mut search = self.start_search(&start_set);
+        let mut best_arrival: Option<(W, usize, usize)> = None;
+
+        while let Some(now) = self.settle_next(&mut search) {
+            if let Some((weight, _, _)) = &best_arrival {
+                if weight.total_cmp(&search.weights[now]).is_le() {
+                    break;
+                }
+            }
+            if end_set.contains(&now) && !start_set.contains(&now) {
+                let route = self.backtrack(&search, now).ok()?;
+                return Some((route, search.weights[now].clone()));
+            }
+
+            for edge in self.graph[now].edges() {
+                let to = self.index(edge.get_to());
+                if !(end_set.contains(&to) && start_set.contains(&to))
+                    || edge.get_weight().is_infinity()
+                {
+                    continue;
+                }
+                let arrival = search.weights[now].add(edge.get_weight());
+                if best_arrival
+                    .as_ref()
+                    .is_none_or(|(weight, _, _)| arrival.total_cmp(weight).is_lt())
+                {
+                    best_arrival = Some((arrival, now, to));
+                }
+            }
+        }
+
+        let (weight, from, to) = best_arrival?;
+        let mut route = self.backtrack(&search, from).ok()?;
+        route.push(self.graph[to]);
+        Some((route, weight))
+    }
+
+    /// Like `find_shorted_path`, but passing through a vertex costs `vertex_cost` of it on
+    /// top of the edge weights. The end vertex is never passed through, so its cost is
+    /// not counted; the start vertex's cost is counted only if `include_start_cost`.
+    pub fn find_shortest_path_with_vertex_costs(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        vertex_cost: impl Fn(&'a V) -> W,
+        include_start_cost: bool,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let options = SearchOptions {
+            vertex_cost: Some(&vertex_cost),
+            include_start_cost,
+            ..SearchOptions::default()
+        };
+        self.search(starts, ends, &options).ok()
+    }
+
+    /// Like `find_shorted_path`, but fails with `Error::NegativeEdgeEncountered` as soon as
+    /// a vertex settled before reaching an end has a negative edge, identifying it by the
+    /// indices of its vertices in `vertices`. Negative edges the search never reaches are
+    /// not an error.
+    pub fn find_shortest_path_checked(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> Result<(Vec<&'a V>, W), Error> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        let mut search = self.start_search(&start_set);
+        while let Some(now) = self.settle_next(&mut search) {
+            if end_set.contains(&now) {
+                let route = self.backtrack(&search, now)?;
+                return Ok((route, search.weights[now].clone()));
+            }
+
+            for edge in self.graph[now].edges() {
+                if edge.get_weight().total_cmp(&W::zero()).is_lt() {
+                    return Err(Error::NegativeEdgeEncountered {
+                        from: now,
+                        to: self.index(edge.get_to()),
+                    });
+                }
+            }
+        }
+
+        Err(Error::NoPath)
+    }
+
+    /// Like `find_shorted_path`, but on the subgraph induced by `allowed`: other vertices
+    /// and every edge touching them are treated as absent.
+    pub fn find_shortest_path_within(
+        &self,
+        allowed: &Set<&'a V>,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let options = SearchOptions {
+            allowed: Some(allowed),
+            ..SearchOptions::default()
+        };
+        self.search(starts, ends, &options).ok()
+    }
+
+    /// Most reliable route from `start` to `end` among the simple routes whose weight is at
+    /// most the shortest weight plus `tolerance`. A route is as reliable as its least
+    /// `reliability` edge; among equally reliable routes the lighter one wins. Every such
+    /// route is enumerated, pruned by the remaining distance to `end`, so a large
+    /// tolerance on a dense graph is expensive.
+    pub fn most_reliable_path_within<R>(
+        &self,
+        start: &'a V,
+        end: &'a V,
+        tolerance: &W,
+        reliability: impl Fn(&'a E) -> R,
+    ) -> Option<(Vec<&'a V>, W)>
+    where
+        R: Ord + Clone,
+    {
+        let (start, end) = (self.index_of(start)?, self.index_of(end)?);
+        if start == end {
+            return Some((vec![self.graph[start]], W::zero()));
+        }
+        let to_end = self.search_all_reverse(&iter::once(end).collect());
+        if !to_end.visiteds[start] {
+            return None;
+        }
+        let limit = to_end.weights[start].add(tolerance);
+
+        let mut best: Option<(Vec<usize>, W, R)> = None;
+        let mut route = vec![start];
+        let mut on_route = vec![false; self.graph.len()];
+        on_route[start] = true;
+        let mut stack = vec![(self.graph[start].edges().into_iter(), W::zero(), None::<R>)];
+
+        while let Some((edges, weight, score)) = stack.last_mut() {
+            let edge = match edges.next() {
+                Some(edge) => edge,
+                None => {
+                    stack.pop();
+                    if let Some(now) = route.pop() {
+                        on_route[now] = false;
+                    }
+                    continue;
+                }
+            };
+            let to = self.index(edge.get_to());
+            if on_route[to] || !to_end.visiteds[to] || edge.get_weight().is_infinity() {
+                continue;
+            }
+            let added_weight = weight.add(edge.get_weight());
+            if added_weight
+                .add(&to_end.weights[to])
+                .total_cmp(&limit)
+                .is_gt()
+            {
+                continue;
+            }
+            let edge_score = reliability(edge);
+            let added_score = match score {
+                Some(score) if *score < edge_score => score.clone(),
+                _ => edge_score,
+            };
+
+            if to == end {
+                let is_better = best.as_ref().is_none_or(|(_, best_weight, best_score)| {
+                    added_score
+                        .cmp(best_score)
+                        .then(best_weight.total_cmp(&added_weight))
+                        .is_gt()
+                });
+                if is_better {
+                    let mut found = route.clone();
+                    found.push(end);
+                    best = Some((found, added_weight, added_score));
+                }
+                continue;
+            }
+            route.push(to);
+            on_route[to] = true;
+            stack.push((
+                self.graph[to].edges().into_iter(),
+                added_weight,
+                Some(added_score),
+            ));
+        }
+
+        let (route, weight, _) = best?;
+        Some((route.into_iter().map(|i| self.graph[i]).collect(), weight))
+    }
+
+    /// Route from `start` to `end` of weight at most `budget` that passes through the most
+    /// vertices of `pois`, the lighter one among routes passing through as many. Routes may
+    /// revisit vertices, so this searches (vertex, points visited) states, pruned by the
+    /// remaining distance to `end`; that is exponential in the number of points, and only
+    /// the first 64 points in vertex order are counted.
+    pub fn best_sightseeing_path(
+        &self,
+        start: &'a V,
+        end: &'a V,
+        budget: &W,
+        pois: &Set<&'a V>,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let (start, end) = (self.index_of(start)?, self.index_of(end)?);
+        let to_end = self.search_all_reverse(&iter::once(end).collect());
+        if !to_end.visiteds[start] {
+            return None;
+        }
+        let mut bits = vec![0u64; self.graph.len()];
+        let poi_indices = (0..self.graph.len()).filter(|&i| pois.contains(self.graph[i]));
+        for (bit, i) in poi_indices.take(64).enumerate() {
+            bits[i] = 1 << bit;
+        }
+
+        let mut states = vec![(start, bits[start])];
+        let mut indices = Map::new();
+        indices.insert(states[0], 0);
+        let mut search = Search::new(1);
+        search.start(0, W::zero());
+
+        let mut best: Option<(u32, usize)> = None;
+        while let Some(now) = search.settle() {
+            let (at, mask) = states[now];
+            if at == end && best.is_none_or(|(count, _)| mask.count_ones() > count) {
+                best = Some((mask.count_ones(), now));
+            }
+
+            let weight_sum = search.weights[now].clone();
+            for edge in self.graph[at].edges() {
+                let to = self.index(edge.get_to());
+                if !to_end.visiteds[to] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let added_weight = weight_sum.add(edge.get_weight());
+                let least_total = added_weight.add(&to_end.weights[to]);
+                if least_total.is_max() || least_total.total_cmp(budget).is_gt() {
+                    continue;
+                }
+
+                let next = (to, mask | bits[to]);
+                let state = match indices.get(&next) {
+                    Some(&state) => state,
+                    None => {
+                        indices.insert(next, states.len());
+                        states.push(next);
+                        search.add_state()
+                    }
+                };
+                search.reach(now, state, added_weight, 0);
+            }
+        }
+
+        let (_, state) = best?;
+        let route = backtrack(&search.backtracker, state).ok()?;
+        let route = route.into_iter().map(|i| self.graph[states[i].0]).collect();
+        Some((route, search.weights[state].clone()))
+    }
+
+    /// Like `find_shorted_path`, but entering a vertex for which `penalty` is `Some` costs
+    /// that much on top of the edge weight, steering routes away from it without
+    /// forbidding it. An infinite penalty makes the vertex unreachable, as if avoided.
+    pub fn find_shortest_path_with_penalties(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        penalty: impl Fn(&'a V) -> Option<W>,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        self.search_path_by(&start_set, &end_set, None, |_, edge, weight_sum| {
+            let added_weight = weight_sum.add(edge.get_weight());
+            match penalty(edge.get_to()) {
+                Some(penalty) if penalty.is_infinity() => None,
+                Some(penalty) => Some(added_weight.add(&penalty)),
+                None => Some(added_weight),
+            }
+        })
+        .ok()
+    }
+
+    /// Like `find_shorted_path`, but for crowding: `load` gives the `(used, capacity)` of
+    /// an edge, an edge with no spare capacity is not taken, and any other costs
+    /// `penalty(used / capacity)` on top of its weight. Loads are a snapshot taken for
+    /// this query; the route found does not add to them.
+    pub fn find_shortest_path_with_load(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        load: impl Fn(&'a E) -> (usize, usize),
+        penalty: impl Fn(f64) -> W,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        self.search_path_by(&start_set, &end_set, None, |_, edge, weight_sum| {
+            let (used, capacity) = load(edge);
+            if used >= capacity {
+                return None;
+            }
+            let utilization = used as f64 / capacity as f64;
+            Some(weight_sum.add(edge.get_weight()).add(&penalty(utilization)))
+        })
+        .ok()
+    }
+
+    /// Vertices other than `start` and `end` whose removal leaves `end` unreachable from
+    /// `start`, in route order. Every route passes through all of them, so only the
+    /// vertices of one shortest route are tried. Empty when `end` is unreachable.
+    pub fn critical_vertices(&self, start: &'a V, end: &'a V) -> Vec<&'a V> {
+        let route = match self.shortest_path(start, end) {
+            Some((route, _)) if route.len() > 2 => route,
+            _ => return vec![],
+        };
+        let start_set = iter::once(self.index(start)).collect();
+        let end_set = iter::once(self.index(end)).collect();
+
+        route[1..route.len() - 1]
+            .iter()
+            .cloned()
+            .filter(|&removed| {
+                let search = self.search_path_by(&start_set, &end_set, None, |_, edge, sum| {
+                    if edge.get_to() == removed {
+                        None
+                    } else {
+                        Some(sum.add(edge.get_weight()))
+                    }
+                });
+                search.is_err()
+            })
+            .collect()
+    }
+
+    /// Shortest route from `start` to `end` sharing no edge with `avoid_path`, a route as
+    /// returned by the other searches. A route names vertices rather than edges, so every
+    /// edge between two consecutive vertices of `avoid_path` is forbidden.
+    pub fn shortest_edge_disjoint_alternative(
+        &self,
+        start: &'a V,
+        end: &'a V,
+        avoid_path: &[&'a V],
+    ) -> Option<(Vec<&'a V>, W)> {
+        let avoided: Set<(usize, usize)> = avoid_path
+            .windows(2)
+            .filter_map(|pair| Some((self.index_of(pair[0])?, self.index_of(pair[1])?)))
+            .collect();
+        let start_set = iter::once(self.index_of(start)?).collect();
+        let end_set = iter::once(self.index_of(end)?).collect();
+
+        self.search_path_by(&start_set, &end_set, None, |from, edge, weight_sum| {
+            if avoided.contains(&(from, self.index(edge.get_to()))) {
+                None
+            } else {
+                Some(weight_sum.add(edge.get_weight()))
+            }
+        })
+        .ok()
+    }
+
+    /// Like `find_shorted_path`, but `tie_break` chooses among predecessors that reach a
+    /// vertex at the same weight (and hop count, with hop tiebreaking): it is called as
+    /// `tie_break(candidate, current)` whenever an edge from a just settled `candidate`
+    /// ties with the vertex's `current` predecessor, and `Ordering::Less` switches to the
+    /// candidate. Only the reconstructed route changes, never its weight.
+    pub fn find_shortest_path_with_tie_break(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        tie_break: impl Fn(&'a V, &'a V) -> Ordering,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let end_set = self.index_set(ends);
+        let mut search = self.start_search(&self.index_set(starts));
+
+        while let Some(now) = search.pop() {
+            search.visiteds[now] = true;
+            if end_set.contains(&now) {
+                let route = self.backtrack(&search, now).ok()?;
+                return Some((route, search.weights[now].clone()));
+            }
+
+            for edge in self.graph[now].edges() {
+                let to = self.index(edge.get_to());
+                if search.visiteds[to] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+
+                let added_weight = search.weights[now].add(edge.get_weight());
+                let hops = if self.hop_tiebreak {
+                    search.hops[now] + 1
+                } else {
+                    0
+                };
+                let ordering = search.weights[to]
+                    .total_cmp(&added_weight)
+                    .then(search.hops[to].cmp(&hops));
+                match (ordering, search.backtracker[to]) {
+                    (Ordering::Greater, _) => {
+                        search.weights[to] = added_weight;
+                        search.backtracker[to] = Some(now);
+                        search.hops[to] = hops;
+                        search.push(to);
+                    }
+                    (Ordering::Equal, Some(current))
+                        if tie_break(self.graph[now], self.graph[current]) == Ordering::Less =>
+                    {
+                        search.backtracker[to] = Some(now);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Like `find_shorted_path`, but when several ends are nearest at the same weight,
+    /// `tie_break_ends` picks among them rather than the order they are settled in:
+    /// `tie_break_ends(a, b)` is `Ordering::Less` when `a` should win over `b`.
+    pub fn find_shortest_path_with_end_tie_break(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        tie_break_ends: impl Fn(&'a V, &'a V) -> Ordering,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let end_set = self.index_set(ends);
+        let mut search = self.start_search(&self.index_set(starts));
+
+        let mut best: Option<usize> = None;
+        while let Some(now) = self.settle_next(&mut search) {
+            if let Some(best) = best {
+                if search.weights[now].total_cmp(&search.weights[best]).is_gt() {
+                    break;
+                }
+            }
+            if !end_set.contains(&now) {
+                continue;
+            }
+            let wins = best.is_none_or(|best| {
+                tie_break_ends(self.graph[now], self.graph[best]) == Ordering::Less
+            });
+            if wins {
+                best = Some(now);
+            }
+        }
+
+        let end = best?;
+        let route = self.backtrack(&search, end).ok()?;
+        Some((route, search.weights[end].clone()))
+    }
+
+    /// Shortest route from any of `starts` to any of `ends` under every constraint set in
+    /// `options`. With `max_hops`, vertices are searched once per number of edges taken to
+    /// reach them, so a lighter route with too many edges cannot hide a valid one.
+    pub fn search(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        options: &SearchOptions<'_, 'a, V, W>,
+    ) -> Result<(Vec<&'a V>, W), Error> {
+        let admits = |vertex: &'a V| {
+            options
+                .allowed
+                .is_none_or(|allowed| allowed.contains(vertex))
+                && options
+                    .avoid_vertices
+                    .is_none_or(|avoided| !avoided.contains(vertex))
+        };
+        let start_set = self.index_set(starts.into_iter().filter(|&v| admits(v)));
+        let end_set = self.index_set(ends.into_iter().filter(|&v| admits(v)));
+        let needs_id = options.avoid_edges.is_some() || options.overrides.is_some();
+
+        let relax = |from: usize, edge: &'a E, weight_sum: &W| {
+            if !admits(edge.get_to()) {
+                return None;
+            }
+            let id = if needs_id {
+                self.edge_id_at(from, edge)
+            } else {
+                None
+            };
+            if let (Some(avoided), Some(id)) = (options.avoid_edges, id) {
+                if avoided.contains(&id) {
+                    return None;
+                }
+            }
+            let weight = match (options.overrides, id) {
+                (Some(overrides), Some(id)) => overrides.get(&id).unwrap_or(edge.get_weight()),
+                _ => edge.get_weight(),
+            };
+            if options
+                .max_edge_weight
+                .as_ref()
+                .is_some_and(|cap| weight.total_cmp(cap).is_gt())
+            {
+                return None;
+            }
+            let added_weight = match options.vertex_cost {
+                Some(cost) if options.include_start_cost || !start_set.contains(&from) => {
+                    weight_sum.add(&cost(self.graph[from])).add(weight)
+                }
+                _ => weight_sum.add(weight),
+            };
+            if options.budget.as_ref().is_some_and(|budget| {
+                added_weight.is_max() || added_weight.total_cmp(budget).is_gt()
+            }) {
+                return None;
+            }
+            Some(added_weight)
+        };
+
+        match options.max_hops {
+            Some(max_hops) => self.search_path_hop_limited(
+                &start_set,
+                &end_set,
+                options.max_iterations,
+                max_hops,
+                relax,
+            ),
+            None => self.search_path_by(&start_set, &end_set, options.max_iterations, relax),
+        }
+    }
+
+    /// Like `find_shorted_path`, but an edge costs `cost_fn(accumulated, edge)` given the
+    /// weight accumulated before taking it. The result is only the shortest route when
+    /// arriving earlier never makes the total at the far end of an edge larger, that is
+    /// when `accumulated + cost_fn(accumulated, edge)` is non-decreasing in `accumulated`.
+    pub fn find_shortest_path_by_cost(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        cost_fn: impl Fn(&W, &'a E) -> W,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        self.search_path_by(&start_set, &end_set, None, |_, edge, weight_sum| {
+            Some(weight_sum.add(&cost_fn(weight_sum, edge)))
+        })
+        .ok()
+    }
+
+    /// Like `find_shorted_path`, but the cost of a route is `initial` combined with each of
+    /// its edge weights in turn, and the route with the least `Accumulator::key` wins.
+    /// Returns the route with its accumulated cost.
+    pub fn find_shortest_path_accumulated<A>(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        initial: A,
+    ) -> Option<(Vec<&'a V>, A)>
+    where
+        A: Accumulator<W>,
+    {
+        let end_set = self.index_set(ends);
+        let mut costs: Vec<Option<A>> = iter::repeat_n(None, self.graph.len()).collect();
+        // The heap orders vertices by the key of their current cost, kept here so that it
+        // is not recomputed on every comparison.
+        let mut keys: Vec<Option<A::Key>> = (0..self.graph.len()).map(|_| None).collect();
+        let mut backtracker = vec![None; self.graph.len()];
+        let mut visiteds = vec![false; self.graph.len()];
+        let mut unvisiteds = IndexedHeap::new(self.graph.len());
+        for start in self.index_set(starts) {
+            costs[start] = Some(initial.clone());
+            keys[start] = Some(initial.key());
+            backtracker[start] = Some(start);
+            unvisiteds.push(start, |a, b| keys[a] < keys[b]);
+        }
+
+        while let Some(now) = unvisiteds.pop(|a, b| keys[a] < keys[b]) {
+            visiteds[now] = true;
+            let cost = costs[now].clone()?;
+            if end_set.contains(&now) {
+                let route = backtrack(&backtracker, now).ok()?;
+                return Some((route.into_iter().map(|i| self.graph[i]).collect(), cost));
+            }
+
+            for edge in self.graph[now].edges() {
+                let to = self.index(edge.get_to());
+                if visiteds[to] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let combined = cost.combine(edge.get_weight());
+                let key = combined.key();
+                if keys[to].as_ref().is_none_or(|current| key < *current) {
+                    costs[to] = Some(combined);
+                    keys[to] = Some(key);
+                    backtracker[to] = Some(now);
+                    unvisiteds.push(to, |a, b| keys[a] < keys[b]);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Lightest route from any of `starts` to any of `ends` whose edges use at most
+    /// `resource_budget` of a second resource in total, such as minutes walked, where
+    /// `resource` gives what an edge uses. Returns the route with its weight and the
+    /// resource it uses. Every vertex keeps each route reaching it that no other reaching
+    /// it beats in both weight and resource, so the work grows with the number of
+    /// distinct resource totals: pseudo-polynomial in the budget.
+    pub fn find_shortest_path_with_resource<R>(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        resource: impl Fn(&'a E) -> R,
+        resource_budget: &R,
+    ) -> Option<(Vec<&'a V>, W, R)>
+    where
+        R: Weight,
+    {
+        let end_set = self.index_set(ends);
+        // Each label is a vertex with the resource used to reach it, in heap entry order.
+        let mut labels: Vec<(usize, R)> = vec![];
+        let mut settled: Vec<Vec<usize>> = vec![vec![]; self.graph.len()];
+        let mut search = Search::new(0);
+        for start in self.index_set(starts) {
+            labels.push((start, R::zero()));
+            let label = search.add_state();
+            search.start(label, W::zero());
+        }
+
+        while let Some(now) = search.settle() {
+            let (at, used) = labels[now].clone();
+            if settled[at]
+                .iter()
+                .any(|&label| labels[label].1.total_cmp(&used).is_le())
+            {
+                continue;
+            }
+            settled[at].push(now);
+
+            if end_set.contains(&at) {
+                let route = backtrack(&search.backtracker, now).ok()?;
+                let route = route.into_iter().map(|i| self.graph[labels[i].0]).collect();
+                return Some((route, search.weights[now].clone(), used));
+            }
+
+            let (weight_sum, hops) = (search.weights[now].clone(), search.hops[now]);
+            for edge in self.graph[at].edges() {
+                if edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let added_used = used.add(&resource(edge));
+                if added_used.is_max() || added_used.total_cmp(resource_budget).is_gt() {
+                    continue;
+                }
+                labels.push((self.index(edge.get_to()), added_used));
+                let label = search.add_state();
+                search.reach(now, label, weight_sum.add(edge.get_weight()), hops + 1);
+            }
+        }
+
+        None
+    }
+
+    /// Like `find_shorted_path`, but edges weighing more than `max_edge_weight` on their
+    /// own are not taken, however light the route through them would be.
+    pub fn find_shortest_path_with_max_edge(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        max_edge_weight: &W,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let options = SearchOptions {
+            max_edge_weight: Some(max_edge_weight.clone()),
+            ..SearchOptions::default()
+        };
+        self.search(starts, ends, &options).ok()
+    }
+
+    /// Like `find_shorted_path`, but every edge in `overrides` weighs the given weight
+    /// instead of its own for this query only.
+    pub fn find_shortest_path_with_overrides(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        overrides: &Map<EdgeId, W>,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let options = SearchOptions {
+            overrides: Some(overrides),
+            ..SearchOptions::default()
+        };
+        self.search(starts, ends, &options).ok()
+    }
+
+    /// Shortest route from any of `starts` to any of `ends` that takes every edge of
+    /// `required`, in the given order. Each leg between consecutive required edges is a
+    /// shortest route of its own, so the result may revisit vertices. `None` when an id
+    /// names no edge or some leg has no route.
+    pub fn shortest_path_with_required_edges(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        required: &[EdgeId],
+    ) -> Option<(Vec<&'a V>, W)> {
+        let mut from_set = self.index_set(starts);
+        let mut route: Vec<&'a V> = vec![];
+        let mut weight = W::zero();
+
+        for id in required {
+            let (from, edge) = self.edge_at(id)?;
+            let to = self.index_of(edge.get_to())?;
+            let to_set = iter::once(from).collect();
+            let (leg, leg_weight) = self
+                .search_path_by(&from_set, &to_set, None, Self::relax_edge)
+                .ok()?;
+            route.pop();
+            route.extend(leg);
+            route.push(self.graph[to]);
+            weight = weight.add(&leg_weight).add(edge.get_weight());
+            from_set = iter::once(to).collect();
+        }
+
+        let end_set = self.index_set(ends);
+        let (leg, leg_weight) = self
+            .search_path_by(&from_set, &end_set, None, Self::relax_edge)
+            .ok()?;
+        route.pop();
+        route.extend(leg);
+        Some((route, weight.add(&leg_weight)))
+    }
+
+    // The index of the vertex the edge leaves, and the edge. An id from `Edge::id` need
+    // not follow the indices here, so the vertex `id.from` names is only searched first.
+    fn edge_at(&self, id: &EdgeId) -> Option<(usize, &'a E)> {
+        let find_from = |from: usize| {
+            self.graph[from]
+                .edges()
+                .into_iter()
+                .find(|&edge| self.edge_id_at(from, edge) == Some(*id))
+                .map(|edge| (from, edge))
+        };
+        if id.from < self.graph.len() {
+            if let Some(found) = find_from(id.from) {
+                return Some(found);
+            }
+        }
+        (0..self.graph.len()).find_map(find_from)
+    }
+
+    /// Every edge paired with the vertex it leaves, in vertex index order and then in the
+    /// order `Vertex::edges` yields them.
+    pub fn all_edges(&self) -> impl Iterator<Item = (&'a V, &'a E)> + '_ {
+        self.graph
+            .iter()
+            .flat_map(|&from| from.edges().into_iter().map(move |edge| (from, edge)))
+    }
+
+    /// Stable id of `edge`, which must be one of the edges of `from`. This is `Edge::id`
+    /// when the edge has one.
+    pub fn edge_id(&self, from: &'a V, edge: &'a E) -> Option<EdgeId> {
+        self.edge_id_at(self.index_of(from)?, edge)
+    }
+
+    fn edge_id_at(&self, from: usize, edge: &'a E) -> Option<EdgeId> {
+        if let Some(id) = edge.id() {
+            return Some(id);
+        }
+        let to_vertex = edge.get_to();
+        let mut index = 0;
+        for candidate in self.graph[from].edges() {
+            if ptr::eq(candidate, edge) {
+                return Some(EdgeId {
+                    from,
+                    to: self.index(to_vertex),
+                    index,
+                });
+            }
+            if candidate.get_to() == to_vertex {
+                index += 1;
+            }
+        }
+        None
+    }
+
+    fn search_path(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        max_iterations: Option<usize>,
+    ) -> Result<(Vec<&'a V>, W), Error> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        self.search_path_by(&start_set, &end_set, max_iterations, Self::relax_edge)
+    }
+
+    fn search_path_by<F>(
+        &self,
+        start_set: &Set<usize>,
+        end_set: &Set<usize>,
+        max_iterations: Option<usize>,
+        relax: F,
+    ) -> Result<(Vec<&'a V>, W), Error>
+    where
+        F: Fn(usize, &'a E, &W) -> Option<W>,
+    {
+        let mut search = self.start_search(start_set);
+
+        while let Some(now) = self.settle_next_by(&mut search, &relax) {
+            if max_iterations.is_some_and(|max| search.iterations > max) {
+                return Err(Error::Timeout);
+            }
+            if end_set.contains(&now) {
+                let route = self.backtrack(&search, now)?;
+                return Ok((route, search.weights[now].clone()));
+            }
+        }
+
+        Err(Error::NoPath)
+    }
+
+    // Searches (vertex, hops) states, where state `hops * n + vertex` was reached with
+    // exactly `hops` edges, so that every vertex may be settled once per edge count.
+    fn search_path_hop_limited<F>(
+        &self,
+        start_set: &Set<usize>,
+        end_set: &Set<usize>,
+        max_iterations: Option<usize>,
+        max_hops: usize,
+        relax: F,
+    ) -> Result<(Vec<&'a V>, W), Error>
+    where
+        F: Fn(usize, &'a E, &W) -> Option<W>,
+    {
+        let n = self.graph.len();
+        let mut search = Search::new(n * (max_hops + 1));
+        for &start in start_set {
+            search.start(start, W::zero());
+        }
+
+        while let Some(now) = search.settle() {
+            if max_iterations.is_some_and(|max| search.iterations > max) {
+                return Err(Error::Timeout);
+            }
+
+            let (vertex, hops) = (now % n, now / n);
+            if end_set.contains(&vertex) {
+                let route = backtrack(&search.backtracker, now)?;
+                let route = route.into_iter().map(|i| self.graph[i % n]).collect();
+                return Ok((route, search.weights[now].clone()));
+            }
+            if hops == max_hops {
+                continue;
+            }
+
+            let weight_sum = search.weights[now].clone();
+            for edge in self.graph[vertex].edges() {
+                let next = (hops + 1) * n + self.index(edge.get_to());
+                if search.visiteds[next] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let added_weight = match relax(vertex, edge, &weight_sum) {
+                    Some(added_weight) => added_weight,
+                    None => continue,
+                };
+                search.reach(now, next, added_weight, hops + 1);
+            }
+        }
+
+        Err(Error::NoPath)
+    }
+
+    /// A* search guided by `heuristic`, which must never overestimate the remaining
+    /// distance to the nearest end and must be consistent along edges. With an
+    /// `upper_bound`, every vertex whose distance plus heuristic exceeds it is pruned, so a
+    /// bound below the shortest route's weight yields `None`.
+    pub fn find_shortest_path_astar(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        heuristic: impl Fn(&'a V) -> W,
+        upper_bound: Option<W>,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+        let mut search = Search::new(self.graph.len());
+        search.estimates = iter::repeat_n(W::infinity(), self.graph.len()).collect();
+        for &start in &start_set {
+            search.estimates[start] = heuristic(self.graph[start]);
+            search.start(start, W::zero());
+        }
+
+        while let Some(now) = search.settle() {
+            if end_set.contains(&now) {
+                let route = self.backtrack(&search, now).ok()?;
+                return Some((route, search.weights[now].clone()));
+            }
+
+            for edge in self.graph[now].edges() {
+                let to_vertex = edge.get_to();
+                let to = self.index(to_vertex);
+                if search.visiteds[to] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+
+                let added_weight = search.weights[now].add(edge.get_weight());
+                if search.weights[to].total_cmp(&added_weight).is_le() {
+                    continue;
+                }
+                let estimate = added_weight.add(&heuristic(to_vertex));
+                if upper_bound
+                    .as_ref()
+                    .is_some_and(|bound| estimate.total_cmp(bound).is_gt())
+                {
+                    continue;
+                }
+
+                search.estimates[to] = estimate;
+                search.reach(now, to, added_weight, 0);
+            }
+        }
+
+        None
+    }
+
+    /// Beam search from `start` to `end` that keeps only the `beam_width` lightest
+    /// vertices of the frontier after each expansion, so memory stays bounded on huge
+    /// graphs. The result may be heavier than the shortest route, or `None` even though a
+    /// route exists, when the vertices leading to it were pruned. A `beam_width` of zero
+    /// is taken as one.
+    pub fn approximate_shortest_path(
+        &self,
+        start: &'a V,
+        end: &'a V,
+        beam_width: usize,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let (start, end) = (self.index_of(start)?, self.index_of(end)?);
+        let mut search = self.start_search(&Set::new());
+        search.weights[start] = W::zero();
+        search.backtracker[start] = Some(start);
+
+        let mut frontier = vec![start];
+        while !frontier.is_empty() {
+            let now = frontier.remove(0);
+            search.visiteds[now] = true;
+            if now == end {
+                let route = self.backtrack(&search, now).ok()?;
+                return Some((route, search.weights[now].clone()));
+            }
+
+            for edge in self.graph[now].edges() {
+                let to = self.index(edge.get_to());
+                if search.visiteds[to] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let added_weight = search.weights[now].add(edge.get_weight());
+                if search.weights[to].total_cmp(&added_weight).is_gt() {
+                    search.weights[to] = added_weight;
+                    search.backtracker[to] = Some(now);
+                    if !frontier.contains(&to) {
+                        frontier.push(to);
+                    }
+                }
+            }
+
+            frontier.sort_by(|&a, &b| search.weights[a].total_cmp(&search.weights[b]));
+            frontier.truncate(beam_width.max(1));
+        }
+
+        None
+    }
+
+    /// Like `find_shorted_path`, but as `(from, edge, to)` steps. Of parallel edges, the
+    /// lightest is given.
+    pub fn find_shortest_path_segments(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> Option<(Vec<Segment<'a, V, E>>, W)> {
+        let (route, weight) = self.search_path(starts, ends, None).ok()?;
+        let segments = route
+            .windows(2)
+            .map(|pair| Some((pair[0], self.lightest_edge(pair[0], pair[1])?, pair[1])))
+            .collect::<Option<_>>()?;
+        Some((segments, weight))
+    }
+
+    /// Like `shortest_path`, but also returns the weight of the lightest edge on the
+    /// route, its narrowest link. The route is still the lightest one overall, not the one
+    /// with the heaviest narrowest link. A route without edges has an infinite bottleneck.
+    pub fn shortest_path_bottleneck(&self, start: &'a V, end: &'a V) -> Option<(Vec<&'a V>, W, W)> {
+        let (segments, weight) =
+            self.find_shortest_path_segments(iter::once(start), iter::once(end))?;
+        let bottleneck = segments
+            .iter()
+            .map(|&(_, edge, _)| edge.get_weight())
+            .min_by(|a, b| a.total_cmp(b))
+            .cloned()
+            .unwrap_or_else(W::infinity);
+        let mut route: Vec<_> = segments.iter().map(|&(from, _, _)| from).collect();
+        route.push(end);
+        Some((route, weight, bottleneck))
+    }
+
+    pub fn shortest_path(&self, start: &'a V, end: &'a V) -> Option<(Vec<&'a V>, W)> {
+        self.search_path(iter::once(start), iter::once(end), None)
+            .ok()
+    }
+
+    /// Shortest routes from `a` to `b` and back from `b` to `a`, which differ on graphs
+    /// with one-way edges. Both directions are forward searches, one from each end.
+    pub fn round_trip(&self, a: &'a V, b: &'a V) -> RoundTrip<'a, V, W> {
+        (self.shortest_path(a, b), self.shortest_path(b, a))
+    }
+
+    /// Like `shortest_path`, but with the route listed from `end` back to `start`.
+    pub fn shortest_path_end_first(&self, start: &'a V, end: &'a V) -> Option<(Vec<&'a V>, W)> {
+        let end = self.index_of(end)?;
+        let search = self.search_until_settled(
+            &self.index_set(iter::once(start)),
+            iter::once(end).collect(),
+        );
+        if !search.visiteds[end] {
+            return None;
+        }
+        let route = backtrack_end_first(&search.backtracker, end).ok()?;
+        let route = route.into_iter().map(|i| self.graph[i]).collect();
+        Some((route, search.weights[end].clone()))
+    }
+
+    pub fn shortest_distance(&self, start: &'a V, end: &'a V) -> Option<W> {
+        self.search_path(iter::once(start), iter::once(end), None)
+            .ok()
+            .map(|(_, weight)| weight)
+    }
+
+    pub fn distance_matrix(&self, pairs: &[(&'a V, &'a V)]) -> Vec<Option<W>> {
+        let mut pairs_by_start = Map::<usize, Vec<usize>>::new();
+        for (i, &(start, _)) in pairs.iter().enumerate() {
+            if let Some(start) = self.index_of(start) {
+                pairs_by_start.entry(start).or_default().push(i);
+            }
+        }
+
+        let mut distances = vec![None; pairs.len()];
+        for (start, pair_indices) in pairs_by_start {
+            let ends: Vec<_> = pair_indices
+                .iter()
+                .map(|&i| self.index_of(pairs[i].1))
+                .collect();
+            let search = self.search_until_settled(
+                &iter::once(start).collect(),
+                ends.iter().flatten().copied().collect(),
+            );
+
+            for (i, end) in pair_indices.into_iter().zip(ends) {
+                distances[i] = end
+                    .filter(|&end| search.visiteds[end])
+                    .map(|end| search.weights[end].clone());
+            }
+        }
+        distances
+    }
+
+    pub fn begin_search(&self, starts: impl IntoIterator<Item = &'a V>) -> Search<W> {
+        self.start_search(&self.index_set(starts))
+    }
+
+    /// Runs the search of `find_shorted_path` and returns every push to and pop from its
+    /// heap in order, for inspecting the exploration order on small graphs.
+    pub fn trace_search(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> Vec<HeapOperation<&'a V>> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        let mut search = self.start_search(&Set::new());
+        search.trace = Some(vec![]);
+        for &start in &start_set {
+            search.weights[start] = W::zero();
+            search.backtracker[start] = Some(start);
+            search.push(start);
+        }
+        while let Some(now) = self.settle_next(&mut search) {
+            if end_set.contains(&now) {
+                break;
+            }
+        }
+
+        search
+            .trace
+            .unwrap_or_default()
+            .into_iter()
+            .map(|operation| match operation {
+                HeapOperation::Push(i) => HeapOperation::Push(self.graph[i]),
+                HeapOperation::Pop(i) => HeapOperation::Pop(self.graph[i]),
+            })
+            .collect()
+    }
+
+    /// Continues `search` until an end is settled, and returns the route to the nearest
+    /// end. Ends settled by earlier calls are answered without searching further, and the
+    /// search can be resumed again afterwards.
+    pub fn resume_search(
+        &self,
+        search: &mut Search<W>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let end_set = self.index_set(ends);
+
+        let settled_end = end_set
+            .iter()
+            .copied()
+            .filter(|&end| search.visiteds[end])
+            .min_by(|&a, &b| search.weights[a].total_cmp(&search.weights[b]));
+        let end = match settled_end {
+            Some(end) => end,
+            None => loop {
+                let now = self.settle_next(search)?;
+                if end_set.contains(&now) {
+                    break now;
+                }
+            },
+        };
+
+        let route = self.backtrack(search, end).ok()?;
+        Some((route, search.weights[end].clone()))
+    }
+
+    pub fn source_tree(&self, start: &'a V) -> SourceTree<W> {
+        self.super_source_tree(iter::once(start))
+    }
+
+    /// Tree of shortest routes from a virtual vertex joined to every one of `starts` by a
+    /// zero-weight edge, as used for Johnson's reweighting. The virtual vertex itself is
+    /// left out, so each start is a root at distance zero and `tree_path` gives routes
+    /// from the nearest start.
+    pub fn super_source_tree(&self, starts: impl IntoIterator<Item = &'a V>) -> SourceTree<W> {
+        SourceTree {
+            search: self.search_all(&self.index_set(starts)),
+            decreased: Map::new(),
+        }
+    }
+
+    /// Route to `end` in `tree`, with the edge weight decreases applied so far.
+    pub fn tree_path(&self, tree: &SourceTree<W>, end: &'a V) -> Option<(Vec<&'a V>, W)> {
+        let end = self.index_of(end)?;
+        if !tree.search.visiteds[end] {
+            return None;
+        }
+        let route = self.backtrack(&tree.search, end).ok()?;
+        Some((route, tree.search.weights[end].clone()))
+    }
+
+    /// Vertices whose distance or predecessor differs between `a` and `b`, such as a tree
+    /// before and after `decrease_edge_weight`, in vertex index order.
+    pub fn tree_diff(
+        &self,
+        a: &SourceTree<W>,
+        b: &SourceTree<W>,
+    ) -> Vec<(&'a V, Change<'a, V, W>)> {
+        let distance = |tree: &SourceTree<W>, i: usize| {
+            Some(tree.search.weights[i].clone()).filter(|_| tree.search.visiteds[i])
+        };
+        let predecessor = |tree: &SourceTree<W>, i: usize| match tree.search.backtracker[i] {
+            Some(prev) if prev != i && tree.search.visiteds[i] => Some(self.graph[prev]),
+            _ => None,
+        };
+
+        let mut changes = vec![];
+        for (i, &v) in self.graph.iter().enumerate() {
+            let change = Change {
+                distance: (distance(a, i), distance(b, i)),
+                predecessor: (predecessor(a, i), predecessor(b, i)),
+            };
+            let distance_changed = match &change.distance {
+                (Some(before), Some(after)) => before.total_cmp(after).is_ne(),
+                (before, after) => before.is_some() != after.is_some(),
+            };
+            if distance_changed || change.predecessor.0 != change.predecessor.1 {
+                changes.push((v, change));
+            }
+        }
+        changes
+    }
+
+    /// Updates `tree` for the edges from `from` to `to` now weighing `new_weight`. Only
+    /// vertices whose distance improves are settled again, rather than searching the whole
+    /// graph. Returns `false` and leaves `tree` as it was when there is no edge from
+    /// `from` to `to`, or when `new_weight` is not lighter than what the edges weigh.
+    pub fn decrease_edge_weight(
+        &self,
+        tree: &mut SourceTree<W>,
+        from: &'a V,
+        to: &'a V,
+        new_weight: W,
+    ) -> bool {
+        let (from, to) = match (self.index_of(from), self.index_of(to)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return false,
+        };
+        match self.pair_weights(&tree.decreased, from).remove(&to) {
+            Some(weight) if weight.total_cmp(&new_weight).is_gt() => {}
+            _ => return false,
+        }
+        tree.decreased.insert((from, to), new_weight.clone());
+
+        let search = &mut tree.search;
+        if !search.visiteds[from] {
+            return true;
+        }
+        let improve = |search: &mut Search<W>, from: usize, to: usize, weight: &W| {
+            let added_weight = search.weights[from].add(weight);
+            if added_weight.total_cmp(&search.weights[to]).is_lt() {
+                search.weights[to] = added_weight;
+                search.backtracker[to] = Some(from);
+                search.hops[to] = search.hops[from] + 1;
+                search.visiteds[to] = true;
+                search.push(to);
+            }
+        };
+        improve(search, from, to, &new_weight);
+
+        while let Some(now) = search.pop() {
+            for (next, weight) in self.pair_weights(&tree.decreased, now) {
+                improve(search, now, next, &weight);
+            }
+        }
+        true
+    }
+
+    // Lightest weight from `from` to each of its neighbors, with decreases applied.
+    fn pair_weights(&self, decreased: &Map<(usize, usize), W>, from: usize) -> Map<usize, W> {
+        let mut weights = Map::<usize, W>::new();
+        for edge in self.graph[from].edges() {
+            let to = self.index(edge.get_to());
+            let weight = edge.get_weight();
+            if weight.is_infinity() {
+                continue;
+            }
+            match weights.get_mut(&to) {
+                Some(lightest) if lightest.total_cmp(weight).is_le() => {}
+                Some(lightest) => *lightest = weight.clone(),
+                None => {
+                    weights.insert(to, weight.clone());
+                }
+            }
+        }
+        for (&(decreased_from, to), weight) in decreased {
+            if decreased_from == from {
+                weights.insert(to, weight.clone());
+            }
+        }
+        weights
+    }
+
+    /// Distances to every end reachable from the nearest start, from one search that runs
+    /// until all ends are settled. Unreachable ends are left out.
+    pub fn distances_to_ends(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> Map<&'a V, W> {
+        let end_set = self.index_set(ends);
+        let search = self.search_until_settled(&self.index_set(starts), end_set.clone());
+
+        end_set
+            .into_iter()
+            .filter(|&end| search.visiteds[end])
+            .map(|end| (self.graph[end], search.weights[end].clone()))
+            .collect()
+    }
+
+    pub fn distances_from(&self, starts: impl IntoIterator<Item = &'a V>) -> Map<&'a V, W> {
+        self.settle_order(starts).collect()
+    }
+
+    pub fn settle_order(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+    ) -> SettleOrder<'_, 'a, V, E, W, S> {
+        SettleOrder {
+            dijkstra: self,
+            search: self.begin_search(starts),
+        }
+    }
+
+    /// Vertices at a distance of at most `budget` from the starts, in settle order. A
+    /// distance for which `Weight::is_max` holds is never within budget.
+    pub fn reachable_within(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        budget: &W,
+    ) -> Vec<(&'a V, W)> {
+        self.settle_order(starts)
+            .take_while(|(_, weight)| !weight.is_max() && weight.total_cmp(budget).is_le())
+            .collect()
+    }
+
+    /// Distances from every vertex that can reach one of `ends`, found by searching
+    /// backward along `Vertex::incoming_edges`.
+    pub fn distances_to(&self, ends: impl IntoIterator<Item = &'a V>) -> Map<&'a V, W> {
+        let search = self.search_all_reverse(&self.index_set(ends));
+
+        self.graph
+            .iter()
+            .zip(search.weights)
+            .zip(search.visiteds)
+            .filter(|&(_, visited)| visited)
+            .map(|((&v, weight), _)| (v, weight))
+            .collect()
+    }
+
+    /// Vertex whose distances to all of `targets` add up to the least, with that sum. A
+    /// multi-source search only finds the nearest target, so this runs one reverse search
+    /// per target rather than one search per vertex. Vertices that cannot reach every
+    /// target are not candidates; ties go to the vertex listed first in the graph.
+    pub fn min_total_distance_vertex(&self, targets: &[&'a V]) -> Option<(&'a V, W)> {
+        let mut totals: Vec<Option<W>> = vec![Some(W::zero()); self.graph.len()];
+        for &target in targets {
+            let search = self.search_all_reverse(&iter::once(self.index(target)).collect());
+            for (i, total) in totals.iter_mut().enumerate() {
+                *total = match total.take() {
+                    Some(sum) if search.visiteds[i] => Some(sum.add(&search.weights[i])),
+                    _ => None,
+                };
+            }
+        }
+
+        let mut best: Option<(&'a V, W)> = None;
+        for (&v, total) in self.graph.iter().zip(totals) {
+            if let Some(total) = total {
+                if best
+                    .as_ref()
+                    .is_none_or(|(_, weight)| total.total_cmp(weight).is_lt())
+                {
+                    best = Some((v, total));
+                }
+            }
+        }
+        best
+    }
+
+    /// Every pair of distinct vertices at a distance of at most `budget`, found by one
+    /// `reachable_within` search per source, as `(from, to, distance)`.
+    pub fn pairs_within(&self, budget: W) -> Vec<(&'a V, &'a V, W)> {
+        let mut pairs = vec![];
+        for &from in &self.graph {
+            for (to, weight) in self.reachable_within(iter::once(from), &budget) {
+                if to != from {
+                    pairs.push((from, to, weight));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Smallest distance within which a fraction `q` of the vertices reachable from
+    /// `start`, not counting `start` itself, lie (the nearest-rank quantile). Returns
+    /// `None` when `q` is outside `[0, 1]` or nothing else is reachable.
+    pub fn distance_quantile(&self, start: &'a V, q: f64) -> Option<W> {
+        if !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        let mut distances: Vec<_> = self
+            .distances_from(iter::once(start))
+            .into_iter()
+            .filter(|&(v, _)| v != start)
+            .map(|(_, weight)| weight)
+            .collect();
+        if distances.is_empty() {
+            return None;
+        }
+        distances.sort_by(W::total_cmp);
+
+        // Rounded up by hand, as `f64::ceil` is not available under `no_std`.
+        let exact = q * distances.len() as f64;
+        let mut rank = exact as usize;
+        if (rank as f64) < exact {
+            rank += 1;
+        }
+        distances.into_iter().nth(rank.saturating_sub(1))
+    }
+
+    /// Largest distance from `v` to any vertex reachable from it. Unreachable vertices
+    /// are ignored rather than making the result infinite.
+    pub fn eccentricity(&self, v: &'a V) -> Option<W> {
+        if !self.v_to_index_map.contains_key(&v) {
+            return None;
+        }
+        self.distances_from(iter::once(v))
+            .into_values()
+            .max_by(W::total_cmp)
+    }
+
+    /// Largest eccentricity over all vertices, so unreachable pairs are ignored as well.
+    /// Returns `None` for an empty graph.
+    pub fn diameter(&self) -> Option<W> {
+        self.graph
+            .iter()
+            .filter_map(|&v| self.eccentricity(v))
+            .max_by(W::total_cmp)
+    }
+
+    /// Fraction of ordered pairs of distinct vertices `(a, b)` where `b` is reachable from
+    /// `a`. Returns 0 when there are fewer than two vertices.
+    pub fn connectivity_ratio(&self) -> f64 {
+        let pairs = self.graph.len() * self.graph.len().saturating_sub(1);
+        if pairs == 0 {
+            return 0.0;
+        }
+        let connected: usize = self
+            .graph
+            .iter()
+            .map(|&v| self.distances_from(iter::once(v)).len() - 1)
+            .sum();
+        connected as f64 / pairs as f64
+    }
+
+    pub fn shortest_path_dag_edges(&self, start: &'a V) -> Vec<(&'a V, &'a V, W)> {
+        let search = self.search_all(&self.index_set(iter::once(start)));
+
+        let mut dag = vec![];
+        for (from, &from_vertex) in self.graph.iter().enumerate() {
+            if !search.visiteds[from] {
+                continue;
+            }
+            for edge in from_vertex.edges() {
+                let to_vertex = edge.get_to();
+                let to = self.index(to_vertex);
+                let weight = edge.get_weight();
+                if weight.is_infinity() || !search.visiteds[to] {
+                    continue;
+                }
+
+                if search.weights[from]
+                    .add(weight)
+                    .total_cmp(&search.weights[to])
+                    .is_eq()
+                {
+                    dag.push((from_vertex, to_vertex, weight.clone()));
+                }
+            }
+        }
+        dag
+    }
+
+    /// Whether `edge` is one of the edges of the shortest route from `start` to `end`.
+    /// Of parallel edges with the same weight, all count as on the route.
+    pub fn is_on_shortest_path(&self, start: &'a V, end: &'a V, edge: &'a E) -> bool {
+        let end = match self.index_of(end) {
+            Some(end) => end,
+            None => return false,
+        };
+        let search = self.search_until_settled(
+            &self.index_set(iter::once(start)),
+            iter::once(end).collect(),
+        );
+        let route = match backtrack(&search.backtracker, end) {
+            Ok(route) => route,
+            Err(_) => return false,
+        };
+
+        let to = self.index(edge.get_to());
+        route.windows(2).any(|pair| {
+            pair[1] == to
+                && self.graph[pair[0]]
+                    .edges()
+                    .into_iter()
+                    .any(|candidate| ptr::eq(candidate, edge))
+                && search.weights[pair[0]].add(edge.get_weight()) == search.weights[to]
+        })
+    }
+
+    /// Pairs every settled vertex other than `start` with the edge its shortest route
+    /// arrives through. Among parallel edges, the first one matching the distance is used.
+    pub fn shortest_path_tree_edges(&self, start: &'a V) -> Vec<(&'a V, &'a E)> {
+        let search = self.search_all(&self.index_set(iter::once(start)));
+        self.tree_edges(&search)
+    }
+
+    fn tree_edges(&self, search: &Search<W>) -> Vec<(&'a V, &'a E)> {
+        let mut tree = vec![];
+        for (to, &to_vertex) in self.graph.iter().enumerate() {
+            let from = match search.backtracker[to] {
+                Some(from) if from != to => from,
+                _ => continue,
+            };
+            let edge = self.graph[from].edges().into_iter().find(|edge| {
+                edge.get_to() == to_vertex
+                    && search.weights[from].add(edge.get_weight()) == search.weights[to]
+            });
+            if let Some(edge) = edge {
+                tree.push((to_vertex, edge));
+            }
+        }
+        tree
+    }
+
+    /// Shortest route that leaves `v` and returns to it: a first edge `v -> u` closed by
+    /// the shortest route back from `u`. Edges are directed, so an edge `u -> v` back is a
+    /// cycle of two edges.
+    pub fn shortest_cycle_through(&self, v: &'a V) -> Option<(Vec<&'a V>, W)> {
+        let start = self.index_of(v)?;
+
+        let mut best: Option<(Vec<&'a V>, W)> = None;
+        for edge in v.edges() {
+            let u = self.index(edge.get_to());
+            if edge.get_weight().is_infinity() {
+                continue;
+            }
+
+            let cycle = if u == start {
+                Some((vec![v, v], edge.get_weight().clone()))
+            } else {
+                let start_set = iter::once(u).collect();
+                let end_set = iter::once(start).collect();
+                self.search_path_by(&start_set, &end_set, None, |_, next, weight_sum| {
+                    Some(weight_sum.add(next.get_weight()))
+                })
+                .ok()
+                .map(|(route, weight)| {
+                    let route = iter::once(v).chain(route).collect();
+                    (route, edge.get_weight().add(&weight))
+                })
+            };
+
+            if let Some((route, weight)) = cycle {
+                if best
+                    .as_ref()
+                    .is_none_or(|(_, best_weight)| weight.total_cmp(best_weight).is_lt())
+                {
+                    best = Some((route, weight));
+                }
+            }
+        }
+        best
+    }
+
+    /// Best route from `start` to `end` other than the shortest one: for each consecutive
+    /// pair of vertices on the shortest route, a search is run with every edge between
+    /// them removed, and the lightest result is kept.
+    pub fn second_shortest_path(&self, start: &'a V, end: &'a V) -> Option<(Vec<&'a V>, W)> {
+        let start_set = self.index_set(iter::once(start));
+        let end_set = self.index_set(iter::once(end));
+        let (shortest, _) = self.shortest_path(start, end)?;
+
+        let mut best: Option<(Vec<&'a V>, W)> = None;
+        for pair in shortest.windows(2) {
+            let (removed_from, removed_to) = (self.index(pair[0]), self.index(pair[1]));
+            let result =
+                self.search_path_by(&start_set, &end_set, None, |from, edge, weight_sum| {
+                    if from == removed_from && self.index(edge.get_to()) == removed_to {
+                        None
+                    } else {
+                        Some(weight_sum.add(edge.get_weight()))
+                    }
+                });
+            if let Ok((route, weight)) = result {
+                if best
+                    .as_ref()
+                    .is_none_or(|(_, best_weight)| weight.total_cmp(best_weight).is_lt())
+                {
+                    best = Some((route, weight));
+                }
+            }
+        }
+        best
+    }
+
+    /// Finds up to `count` routes from `start` to `end` with the penalty method: after
+    /// each search, the weight of every edge on the found route is added once more, and
+    /// the search is run again. A route is kept only if its overlap with every route kept
+    /// so far is below `overlap_threshold`, where the overlap of a route with another is
+    /// the fraction of its edges (consecutive vertex pairs) that the other also uses.
+    ///
+    /// The first route is always the shortest one. The returned weights are the real,
+    /// unpenalized route weights. At most four penalized searches are run per route, and
+    /// the routes found so far are returned once those fail.
+    pub fn alternatives(
+        &self,
+        start: &'a V,
+        end: &'a V,
+        count: usize,
+        overlap_threshold: f64,
+    ) -> Vec<(Vec<&'a V>, W)> {
+        self.alternatives_iter(start, end, overlap_threshold)
+            .take(count)
+            .collect()
+    }
+
+    /// The routes of `alternatives` one at a time, for asking for another route only when
+    /// needed. Each call to `next` runs up to four searches.
+    pub fn alternatives_iter(
+        &self,
+        start: &'a V,
+        end: &'a V,
+        overlap_threshold: f64,
+    ) -> AlternativesIterator<'_, 'a, V, E, W, S> {
+        AlternativesIterator {
+            dijkstra: self,
+            start_set: self.index_set(iter::once(start)),
+            end_set: self.index_set(iter::once(end)),
+            overlap_threshold,
+            penalties: Map::new(),
+            routes_edges: vec![],
+        }
+    }
+
+    /// Connected components of the undirected projection of the graph, in order of their
+    /// lowest vertex index, each listing its vertices in index order.
+    pub fn connected_components(&self) -> Vec<Vec<&'a V>> {
+        fn root(parents: &mut [usize], mut v: usize) -> usize {
+            while parents[v] != v {
+                parents[v] = parents[parents[v]];
+                v = parents[v];
+            }
+            v
+        }
+
+        let mut parents: Vec<_> = (0..self.graph.len()).collect();
+        for (from, &from_vertex) in self.graph.iter().enumerate() {
+            for edge in from_vertex.edges() {
+                let a = root(&mut parents, from);
+                let b = root(&mut parents, self.index(edge.get_to()));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut components: Vec<Vec<&'a V>> = vec![];
+        let mut component_of_root = Map::new();
+        for (v, &vertex) in self.graph.iter().enumerate() {
+            let root = root(&mut parents, v);
+            let component = *component_of_root.entry(root).or_insert_with(|| {
+                components.push(vec![]);
+                components.len() - 1
+            });
+            components[component].push(vertex);
+        }
+        components
+    }
+
+    /// Strongly connected components by Tarjan's algorithm, in reverse topological order
+    /// of the condensation: no component has an edge into a later one.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<&'a V>> {
+        let successors: Vec<Vec<usize>> = self
+            .graph
+            .iter()
+            .map(|&v| {
+                v.edges()
+                    .into_iter()
+                    .map(|edge| self.index(edge.get_to()))
+                    .collect()
+            })
+            .collect();
+
+        let mut order = vec![None; self.graph.len()];
+        let mut lowlinks = vec![0; self.graph.len()];
+        let mut on_stack = vec![false; self.graph.len()];
+        let mut stack = vec![];
+        let mut components = vec![];
+        let mut next_order = 0;
+
+        for root in 0..self.graph.len() {
+            if order[root].is_some() {
+                continue;
+            }
+            order[root] = Some(next_order);
+            lowlinks[root] = next_order;
+            next_order += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            let mut frames = vec![(root, 0)];
+
+            while let Some(&mut (v, ref mut next)) = frames.last_mut() {
+                if let Some(&w) = successors[v].get(*next) {
+                    *next += 1;
+                    match order[w] {
+                        None => {
+                            order[w] = Some(next_order);
+                            lowlinks[w] = next_order;
+                            next_order += 1;
+                            stack.push(w);
+                            on_stack[w] = true;
+                            frames.push((w, 0));
+                        }
+                        Some(w_order) if on_stack[w] => {
+                            lowlinks[v] = lowlinks[v].min(w_order);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                frames.pop();
+                if let Some(&(parent, _)) = frames.last() {
+                    lowlinks[parent] = lowlinks[parent].min(lowlinks[v]);
+                }
+                if Some(lowlinks[v]) == order[v] {
+                    let mut component = vec![];
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(self.graph[w]);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Minimum spanning forest of the undirected projection of the graph, by Prim's
+    /// algorithm. Edges are returned as `(from, to, weight)` in their own direction, one
+    /// tree after another in order of the lowest vertex index of each tree. Infinite edges
+    /// are blocked, so vertices joined only by them end up in separate trees.
+    pub fn minimum_spanning_tree(&self) -> Vec<(&'a V, &'a V, W)> {
+        let mut neighbors: Vec<Vec<(usize, usize, &'a E)>> = vec![vec![]; self.graph.len()];
+        for (from, &from_vertex) in self.graph.iter().enumerate() {
+            for edge in from_vertex.edges() {
+                if edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let to = self.index(edge.get_to());
+                neighbors[from].push((to, from, edge));
+                neighbors[to].push((from, from, edge));
+            }
+        }
+
+        let mut tree = vec![];
+        let mut in_tree = vec![false; self.graph.len()];
+        let mut candidates: Vec<(usize, Option<(usize, &'a E)>)> = vec![];
+        let mut unvisiteds = BinaryHeap::new();
+
+        for root in 0..self.graph.len() {
+            if in_tree[root] {
+                continue;
+            }
+            candidates.push((root, None));
+            push_unvisited(&mut unvisiteds, candidates.len() - 1, W::zero(), 0);
+
+            while let Some(pair) = unvisiteds.pop() {
+                let (now, via) = candidates[pair.index];
+                if in_tree[now] {
+                    continue;
+                }
+                in_tree[now] = true;
+                if let Some((from, edge)) = via {
+                    tree.push((self.graph[from], edge.get_to(), pair.weight));
+                }
+
+                for &(other, from, edge) in &neighbors[now] {
+                    if in_tree[other] {
+                        continue;
+                    }
+                    candidates.push((other, Some((from, edge))));
+                    push_unvisited(
+                        &mut unvisiteds,
+                        candidates.len() - 1,
+                        edge.get_weight().clone(),
+                        0,
+                    );
+                }
+            }
+        }
+        tree
+    }
+
+    fn route_weight(&self, route: &[&'a V]) -> W {
+        route.windows(2).fold(W::zero(), |sum, pair| {
+            match self.lightest_edge(pair[0], pair[1]) {
+                Some(edge) => sum.add(edge.get_weight()),
+                None => W::infinity(),
+            }
+        })
+    }
+
+    fn lightest_edge(&self, from: &'a V, to: &'a V) -> Option<&'a E> {
+        from.edges()
+            .into_iter()
+            .filter(|edge| edge.get_to() == to)
+            .min_by(|a, b| a.get_weight().total_cmp(b.get_weight()))
+    }
+
+    fn index(&self, v: &V) -> usize {
+        self.index_of(v)
+            .expect("edge points to a vertex outside the graph")
+    }
+
+    fn debug_assert_indices(&self) {
+        debug_assert!(
+            self.graph
+                .iter()
+                .enumerate()
+                .all(|(i, &v)| self.v_to_index_map.get(&v) == Some(i)),
+            "vertex index map is out of sync with the vertex list"
+        );
+    }
+
+    fn index_set(&self, list: impl IntoIterator<Item = &'a V>) -> Set<usize> {
+        list.into_iter().filter_map(|v| self.index_of(v)).collect()
+    }
+
+    fn start_search(&self, start_set: &Set<usize>) -> Search<W> {
+        let mut search = Search::new(self.graph.len());
+        for &i in start_set {
+            search.start(i, W::zero());
+        }
+        search
+    }
+
+    fn search_all(&self, start_set: &Set<usize>) -> Search<W> {
+        let mut search = self.start_search(start_set);
+        while self.settle_next(&mut search).is_some() {}
+        search
+    }
+
+    fn search_all_reverse(&self, end_set: &Set<usize>) -> Search<W> {
+        let mut search = self.start_search(end_set);
+        while let Some(now) = search.pop() {
+            search.visiteds[now] = true;
+
+            for (from_vertex, edge) in self.graph[now].incoming_edges(&self.graph) {
+                let from = self.index(from_vertex);
+                if search.visiteds[from] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+
+                let added_weight = search.weights[now].add(edge.get_weight());
+                if search.weights[from].total_cmp(&added_weight).is_gt() {
+                    search.weights[from] = added_weight;
+                    search.backtracker[from] = Some(now);
+                    search.hops[from] = search.hops[now] + 1;
+                    search.push(from);
+                }
+            }
+        }
+        search
+    }
+
+    fn search_until_settled(&self, start_set: &Set<usize>, mut targets: Set<usize>) -> Search<W> {
+        let mut search = self.start_search(start_set);
+        while !targets.is_empty() {
+            match self.settle_next(&mut search) {
+                Some(now) => targets.remove(&now),
+                None => break,
+            };
+        }
+        search
+    }
+
+    fn settle_next(&self, search: &mut Search<W>) -> Option<usize> {
+        self.settle_next_by(search, &Self::relax_edge)
+    }
+
+    fn settle_next_by<F>(&self, search: &mut Search<W>, relax: &F) -> Option<usize>
+    where
+        F: Fn(usize, &'a E, &W) -> Option<W>,
+    {
+        let now = search.settle()?;
+
+        let weight_sum = search.weights[now].clone();
+        for edge in self.graph[now].edges() {
+            let to = self.index(edge.get_to());
+
+            if search.visiteds[to] || edge.get_weight().is_infinity() {
+                continue;
+            }
+
+            let added_weight = match relax(now, edge, &weight_sum) {
+                Some(added_weight) => added_weight,
+                None => continue,
+            };
+
+            let hops = if self.hop_tiebreak {
+                search.hops[now] + 1
+            } else {
+                0
+            };
+            search.reach(now, to, added_weight, hops);
+        }
+
+        Some(now)
+    }
+
+    fn relax_edge(_: usize, edge: &'a E, weight_sum: &W) -> Option<W> {
+        Some(weight_sum.add(edge.get_weight()))
+    }
+
+    /// Edge-expanded search, where a state is a vertex together with the edge it was
+    /// reached through, so that `cost` can price an edge by the one taken before it (`None`
+    /// at a start). Returns the route, the edges taken and the weight.
+    fn search_edge_states<F>(
+        &self,
+        start_set: &Set<usize>,
+        end_set: &Set<usize>,
+        cost: F,
+    ) -> Option<EdgeRoute<'a, V, E, W>>
+    where
+        F: Fn(Option<&'a E>, &'a E) -> Option<W>,
+    {
+        let mut edges = vec![];
+        let mut first_edges = Vec::with_capacity(self.graph.len() + 1);
+        for &from_vertex in &self.graph {
+            first_edges.push(edges.len());
+            for edge in from_vertex.edges() {
+                edges.push((self.index(edge.get_to()), edge));
+            }
+        }
+        first_edges.push(edges.len());
+
+        // States below `edges.len()` arrived through that edge, the rest are the starts.
+        let state_count = edges.len() + self.graph.len();
+        let vertex_of = |state: usize| {
+            if state < edges.len() {
+                edges[state].0
+            } else {
+                state - edges.len()
+            }
+        };
+
+        let mut search = Search::new(state_count);
+        for &start in start_set {
+            search.start(edges.len() + start, W::zero());
+        }
+
+        while let Some(now) = search.settle() {
+            let at = vertex_of(now);
+            if end_set.contains(&at) {
+                let states = backtrack(&search.backtracker, now).ok()?;
+                let route = states.iter().map(|&s| self.graph[vertex_of(s)]).collect();
+                let route_edges = states
+                    .iter()
+                    .filter(|&&s| s < edges.len())
+                    .map(|&s| edges[s].1)
+                    .collect();
+                return Some((route, route_edges, search.weights[now].clone()));
+            }
+
+            let prev = edges.get(now).map(|&(_, edge)| edge);
+            let first = first_edges[at];
+            for (next, &(_, edge)) in edges[first..first_edges[at + 1]].iter().enumerate() {
+                let next = first + next;
+                if search.visiteds[next] || edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let added_weight = match cost(prev, edge) {
+                    Some(cost) => search.weights[now].add(&cost),
+                    None => continue,
+                };
+                search.reach(now, next, added_weight, 0);
+            }
+        }
+
+        None
+    }
+
+    fn backtrack(&self, search: &Search<W>, end: usize) -> Result<Vec<&'a V>, Error> {
+        let route = backtrack(&search.backtracker, end)?;
+        Ok(route.into_iter().map(|i| self.graph[i]).collect())
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: LineEdge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    /// Line graph of this graph, whose vertices are the edges, so that transfers become
+    /// ordinary edges. Riding edge `e` and then edge `f` is an edge from `Edge(e)` to
+    /// `Edge(f)` weighing `f`, plus `transfer_penalty` when their lines differ. A route
+    /// from `Departure(a)` to `Arrival(b)` is then a route from `a` to `b` weighing what
+    /// `find_transit_path` weighs it.
+    pub fn to_line_graph<'b>(&self, transfer_penalty: &W) -> Graph<'b, LineNode, W> {
+        let edges: Vec<Vec<_>> = self
+            .graph
+            .iter()
+            .enumerate()
+            .map(|(from, &vertex)| {
+                vertex
+                    .edges()
+                    .into_iter()
+                    .filter(|edge| !edge.get_weight().is_infinity())
+                    .filter_map(|edge| Some((self.edge_id_at(from, edge)?, edge)))
+                    .collect()
+            })
+            .collect();
+
+        let mut line_graph = Graph::new();
+        for (from, from_edges) in edges.iter().enumerate() {
+            line_graph.add_vertex(LineNode::Departure(from));
+            line_graph.add_vertex(LineNode::Arrival(from));
+            for &(id, edge) in from_edges {
+                let to = self.index(edge.get_to());
+                let node = LineNode::Edge(id);
+                line_graph.add_edge(LineNode::Departure(from), node, edge.get_weight().clone());
+                line_graph.add_edge(node, LineNode::Arrival(to), W::zero());
+                for &(next_id, next) in &edges[to] {
+                    let weight = if edge.line() != next.line() {
+                        next.get_weight().add(transfer_penalty)
+                    } else {
+                        next.get_weight().clone()
+                    };
+                    line_graph.add_edge(node, LineNode::Edge(next_id), weight);
+                }
+            }
+        }
+        line_graph
+    }
+
+    /// Shortest route where changing lines between two consecutive edges costs an extra
+    /// `transfer_penalty`, returned with its weight and number of transfers.
+    pub fn find_transit_path(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        transfer_penalty: &W,
+    ) -> Option<(Vec<&'a V>, W, usize)> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        let (route, edges, weight) =
+            self.search_edge_states(&start_set, &end_set, |prev, next| {
+                let weight = next.get_weight();
+                match prev {
+                    Some(prev) if prev.line() != next.line() => Some(weight.add(transfer_penalty)),
+                    _ => Some(weight.clone()),
+                }
+            })?;
+
+        let transfers = edges
+            .windows(2)
+            .filter(|pair| pair[0].line() != pair[1].line())
+            .count();
+        Some((route, weight, transfers))
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: ModeEdge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    /// Shortest route where switching from one mode to another between two consecutive
+    /// edges costs an extra `mode_change_cost(from_mode, to_mode)`. Returns the route, its
+    /// weight and the route split into maximal single-mode segments, each with its mode.
+    pub fn find_multimodal_path(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        mode_change_cost: impl Fn(&E::Mode, &E::Mode) -> W,
+    ) -> Option<MultimodalRoute<'a, V, E::Mode, W>> {
+        let start_set = self.index_set(starts);
+        let end_set = self.index_set(ends);
+
+        let (route, edges, weight) =
+            self.search_edge_states(&start_set, &end_set, |prev, next| {
+                let weight = next.get_weight();
+                match prev {
+                    Some(prev) if prev.mode() != next.mode() => {
+                        Some(weight.add(&mode_change_cost(prev.mode(), next.mode())))
+                    }
+                    _ => Some(weight.clone()),
+                }
+            })?;
+
+        let mut segments: ModeSegments<'a, V, E::Mode> = vec![];
+        for (i, &edge) in edges.iter().enumerate() {
+            match segments.last_mut() {
+                Some((mode, vertices)) if *mode == edge.mode() => vertices.push(route[i + 1]),
+                _ => segments.push((edge.mode(), vec![route[i], route[i + 1]])),
+            }
+        }
+        Some((route, weight, segments))
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: ScheduledEdge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    /// Earliest arrival at any of `ends` for a rider at `starts` at time zero, who waits
+    /// for a scheduled edge at most `max_wait` and cannot take one that already departed.
+    /// Returns the route with its arrival time. A vertex may be passed at several times,
+    /// since a later arrival may catch a connection within `max_wait` that an earlier one
+    /// would wait too long for. Once every scheduled edge has departed, only the earliest
+    /// arrival at each vertex is kept, so the search ends even on graphs with cycles.
+    pub fn find_shortest_path_with_max_wait(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+        max_wait: &W,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let end_set = self.index_set(ends);
+        let last_departure = self
+            .graph
+            .iter()
+            .flat_map(|&vertex| vertex.edges())
+            .filter_map(|edge| edge.departure().cloned())
+            .max_by(W::total_cmp);
+        let after_schedule = |time: &W| {
+            last_departure
+                .as_ref()
+                .is_none_or(|last| time.total_cmp(last).is_gt())
+        };
+
+        // Each label is a vertex with the time it is reached at, in heap entry order.
+        let mut labels: Vec<(usize, W)> = vec![];
+        let mut settled: Vec<Vec<usize>> = vec![vec![]; self.graph.len()];
+        let mut search = Search::new(0);
+        for start in self.index_set(starts) {
+            labels.push((start, W::zero()));
+            let label = search.add_state();
+            search.start(label, W::zero());
+        }
+
+        while let Some(now) = search.settle() {
+            let (at, time) = labels[now].clone();
+            if settled[at]
+                .iter()
+                .any(|&label| after_schedule(&time) || labels[label].1.total_cmp(&time).is_eq())
+            {
+                continue;
+            }
+            settled[at].push(now);
+
+            if end_set.contains(&at) {
+                let route = backtrack(&search.backtracker, now).ok()?;
+                let route = route.into_iter().map(|i| self.graph[labels[i].0]).collect();
+                return Some((route, time));
+            }
+
+            let hops = search.hops[now];
+            for edge in self.graph[at].edges() {
+                if edge.get_weight().is_infinity() {
+                    continue;
+                }
+                let arrival = match edge.departure() {
+                    Some(departure)
+                        if departure.total_cmp(&time).is_lt()
+                            || time.add(max_wait).total_cmp(departure).is_lt() =>
+                    {
+                        continue
+                    }
+                    Some(departure) => departure.add(edge.get_weight()),
+                    None => time.add(edge.get_weight()),
+                };
+                labels.push((self.index(edge.get_to()), arrival.clone()));
+                let label = search.add_state();
+                search.reach(now, label, arrival, hops + 1);
+            }
+        }
+
+        None
+    }
+
+    /// Earliest arrival at the last of `stops` for a rider leaving `start` at time zero
+    /// and passing through every stop in order. A stop is `(vertex, opens, closes)`: the
+    /// rider must arrive by `closes`, and waits for `opens` before going on when early.
+    /// Each leg is an earliest-arrival search of its own, so `None` means some stop cannot
+    /// be reached before it closes. Returns the whole route with its arrival time.
+    pub fn find_itinerary_with_windows(
+        &self,
+        start: &'a V,
+        stops: &[(&'a V, W, W)],
+    ) -> Option<(Vec<&'a V>, W)> {
+        let mut from_set = self.index_set(iter::once(start));
+        let mut route = vec![start];
+        let mut time = W::zero();
+
+        for (stop, opens, closes) in stops {
+            let to_set = self.index_set(iter::once(*stop));
+            let (leg, arrival) = self.earliest_arrival(&from_set, &to_set, time)?;
+            if arrival.total_cmp(closes).is_gt() {
+                return None;
+            }
+            route.pop();
+            route.extend(leg);
+            time = if arrival.total_cmp(opens).is_lt() {
+                opens.clone()
+            } else {
+                arrival
+            };
+            from_set = to_set;
+        }
+        Some((route, time))
+    }
+
+    // Search by arrival time, leaving the starts at `departure`. A scheduled edge is only
+    // taken before it departs.
+    fn earliest_arrival(
+        &self,
+        start_set: &Set<usize>,
+        end_set: &Set<usize>,
+        departure: W,
+    ) -> Option<(Vec<&'a V>, W)> {
+        let mut search = self.start_search(&Set::new());
+        for &start in start_set {
+            search.weights[start] = departure.clone();
+            search.backtracker[start] = Some(start);
+            search.push(start);
+        }
+
+        let relax = |_, edge: &'a E, now: &W| match edge.departure() {
+            Some(departure) if departure.total_cmp(now).is_lt() => None,
+            Some(departure) => Some(departure.add(edge.get_weight())),
+            None => Some(now.add(edge.get_weight())),
+        };
+        while let Some(now) = self.settle_next_by(&mut search, &relax) {
+            if end_set.contains(&now) {
+                let route = self.backtrack(&search, now).ok()?;
+                return Some((route, search.weights[now].clone()));
+            }
+        }
+        None
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight + fmt::Debug,
+    S: IndexHasher,
+{
+    /// The graph in Graphviz DOT, with the shortest-path tree from `start` drawn bold and
+    /// every other edge faint. Edges are labeled with their weights and vertices with
+    /// `label` of them and, when reachable, their distance from `start`.
+    pub fn tree_to_dot(&self, start: &'a V, label: impl Fn(&'a V) -> String) -> String {
+        let search = self.search_all(&self.index_set(iter::once(start)));
+        let tree: Set<*const E> = self
+            .tree_edges(&search)
+            .into_iter()
+            .map(|(_, edge)| edge as *const E)
+            .collect();
+
+        let mut dot = String::from("digraph {\n");
+        for (i, &v) in self.graph.iter().enumerate() {
+            if search.visiteds[i] {
+                writeln!(
+                    dot,
+                    "    {} [label=\"{} ({:?})\"];",
+                    i,
+                    label(v),
+                    search.weights[i]
+                )
+            } else {
+                writeln!(dot, "    {} [label=\"{}\"];", i, label(v))
+            }
+            .unwrap();
+        }
+        for (from, &from_vertex) in self.graph.iter().enumerate() {
+            for edge in from_vertex.edges() {
+                let style = if tree.contains(&(edge as *const E)) {
+                    "style=bold"
+                } else {
+                    "color=gray"
+                };
+                writeln!(
+                    dot,
+                    "    {} -> {} [label=\"{:?}\", {}];",
+                    from,
+                    self.index(edge.get_to()),
+                    edge.get_weight(),
+                    style
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: Weight + Into<f64>,
+    S: IndexHasher,
+{
+    /// Mean distance over the ordered pairs of distinct vertices where the second is
+    /// reachable from the first. Unreachable pairs are left out rather than counted as
+    /// infinite. Returns `None` when no such pair exists.
+    pub fn average_path_length(&self) -> Option<f64> {
+        let mut total = 0.0;
+        let mut pairs = 0;
+        for &start in &self.graph {
+            for (end, weight) in self.settle_order(iter::once(start)) {
+                if end != start {
+                    total += weight.into();
+                    pairs += 1;
+                }
+            }
+        }
+        if pairs == 0 {
+            None
+        } else {
+            Some(total / pairs as f64)
+        }
+    }
+}
+
+// Route weight added up in `W::Wide`, for `find_shortest_path_widened`.
+struct Widened<W>(W::Wide)
+where
+    W: WideWeight;
+
+impl<W> Clone for Widened<W>
+where
+    W: WideWeight,
+{
+    fn clone(&self) -> Self {
+        Widened(self.0.clone())
+    }
+}
+
+impl<W> Accumulator<W> for Widened<W>
+where
+    W: WideWeight,
+{
+    type Key = TotalOrder<W::Wide>;
+
+    fn combine(&self, edge_weight: &W) -> Self {
+        Widened(self.0.add(&edge_weight.widen()))
+    }
+
+    fn key(&self) -> Self::Key {
+        TotalOrder(self.0.clone())
+    }
+}
+
+// Weight ordered by `Weight::total_cmp`, for use where `Ord` is needed.
+struct TotalOrder<W>(W);
+
+impl<W> PartialEq for TotalOrder<W>
+where
+    W: Weight,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W> Eq for TotalOrder<W> where W: Weight {}
+
+impl<W> PartialOrd for TotalOrder<W>
+where
+    W: Weight,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W> Ord for TotalOrder<W>
+where
+    W: Weight,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: WideWeight,
+    S: IndexHasher,
+{
+    /// Like `find_shorted_path`, but route weights are added up in `W::Wide`, so a long
+    /// route whose weight `W` cannot hold is still found, with its exact weight. Use
+    /// `WideWeight::narrow` to bring the weight back to `W`.
+    pub fn find_shortest_path_widened(
+        &self,
+        starts: impl IntoIterator<Item = &'a V>,
+        ends: impl IntoIterator<Item = &'a V>,
+    ) -> Option<(Vec<&'a V>, W::Wide)> {
+        let (route, Widened(weight)) =
+            self.find_shortest_path_accumulated(starts, ends, Widened::<W>(W::Wide::zero()))?;
+        Some((route, weight))
+    }
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: Edge<'a, V, W> + 'a,
+    W: BucketWeight,
+    S: IndexHasher,
+{
+    /// Counts the vertices reachable from `start` per distance bucket, where index `i`
+    /// counts distances in `[i * bucket_size, (i + 1) * bucket_size)`.
+    ///
+    /// Fails with `Error::InvalidBucketSize` when `bucket_size` is not positive, and with
+    /// `Error::CapacityOverflow` when the buckets up to the farthest distance do not fit
+    /// in memory.
+    pub fn distance_histogram(&self, start: &'a V, bucket_size: &W) -> Result<Vec<usize>, Error> {
+        let mut histogram = vec![];
+        for weight in self.distances_from(iter::once(start)).into_values() {
+            let bucket = weight.bucket(bucket_size).ok_or(Error::InvalidBucketSize)?;
+            let len = bucket.checked_add(1).ok_or(Error::CapacityOverflow)?;
+            if histogram.len() < len {
+                histogram
+                    .try_reserve(len - histogram.len())
+                    .map_err(|_| Error::CapacityOverflow)?;
+                histogram.resize(len, 0);
+            }
+            histogram[bucket] += 1;
+        }
+        Ok(histogram)
+    }
+}
+
+/// Walks `backtracker` from `end` to a root, which is a vertex recorded as its own
+/// predecessor, and returns the indices in root-to-end order.
+///
+/// Fails with `Error::CorruptedBacktrack` when a step has no predecessor or the walk
+/// never reaches a root.
+pub fn backtrack(backtracker: &[Option<usize>], end: usize) -> Result<Vec<usize>, Error> {
+    let mut route = backtrack_end_first(backtracker, end)?;
+    route.reverse();
+    Ok(route)
+}
+
+/// Like `backtrack`, but returns the indices in end-to-root order, the order the walk
+/// visits them in.
+pub fn backtrack_end_first(backtracker: &[Option<usize>], end: usize) -> Result<Vec<usize>, Error> {
+    let mut route = vec![end];
+
+    let mut now = end;
+    loop {
+        let prev = match backtracker.get(now) {
+            Some(&Some(prev)) => prev,
+            _ => return Err(Error::CorruptedBacktrack),
+        };
+        if prev == now {
+            break;
+        }
+        if route.len() > backtracker.len() {
+            return Err(Error::CorruptedBacktrack);
+        }
+        route.push(prev);
+        now = prev;
+    }
+
+    Ok(route)
+}
+
+/// Shortest route from `start` to the first state satisfying `is_goal`, on a graph given
+/// only by `neighbors`, which lists the states one edge away with the edge weights.
+/// States are discovered as the search reaches them, so the graph may be too large to
+/// list or even infinite, as long as a goal is reachable.
+pub fn implicit<V, W>(
+    start: V,
+    neighbors: impl Fn(&V) -> Vec<(V, W)>,
+    is_goal: impl Fn(&V) -> bool,
+) -> Option<(Vec<V>, W)>
+where
+    V: Key + Clone,
+    W: Weight,
+{
+    let mut indices = Map::new();
+    let mut states = vec![start.clone()];
+    indices.insert(start, 0);
+    let mut search = Search::new(1);
+    search.start(0, W::zero());
+
+    while let Some(now) = search.settle() {
+        if is_goal(&states[now]) {
+            let route = backtrack(&search.backtracker, now).ok()?;
+            let route = route.into_iter().map(|i| states[i].clone()).collect();
+            return Some((route, search.weights[now].clone()));
+        }
+
+        let (weight_sum, hops) = (search.weights[now].clone(), search.hops[now]);
+        for (next, weight) in neighbors(&states[now]) {
+            if weight.is_infinity() {
+                continue;
+            }
+            let to = match indices.get(&next) {
+                Some(&to) => to,
+                None => {
+                    let to = states.len();
+                    indices.insert(next.clone(), to);
+                    states.push(next);
+                    search.add_state()
+                }
+            };
+            search.reach(now, to, weight_sum.add(&weight), hops + 1);
+        }
+    }
+
+    None
+}
+
+impl<'a, V, E, W, S> Dijkstra<'a, V, E, W, S>
+where
+    V: Vertex<'a, E, W> + 'a,
+    E: HeadwayEdge<'a, V, W> + 'a,
+    W: Weight,
+    S: IndexHasher,
+{
+    /// Route with the least expected travel time when lines run at their headways instead
+    /// of on timetables. Boarding a line, at the start or by changing from another one,
+    /// adds `expected_wait(headway)`, which is half the headway for random arrivals.
+    /// Returns the route and its expected travel time.
+    pub fn shortest_path_matching_schedule_frequency(
+        &self,
+        start: &'a V,
+        end: &'a V,
+        expected_wait: impl Fn(&W) -> W,
+    ) -> Option<Route<'a, V, W>> {
+        let start_set = self.index_set(iter::once(start));
+        let end_set = self.index_set(iter::once(end));
+
+        let (route, _, weight) = self.search_edge_states(&start_set, &end_set, |prev, next| {
+            let weight = next.get_weight();
+            let boards = prev.is_none_or(|prev| prev.line() != next.line());
+            match next.headway() {
+                Some(headway) if boards => Some(weight.add(&expected_wait(headway))),
+                _ => Some(weight.clone()),
+            }
+        })?;
+        Some((route, weight))
     }
 }
@@ -0,0 +1,18 @@
+#[cfg(feature = "no_std")]
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Formats `path` as a GeoJSON `LineString` geometry, with `coord_fn` giving the
+/// `(longitude, latitude)` of each vertex. Coordinates are expected to be finite.
+pub fn path_to_geojson<V>(path: &[&V], coord_fn: impl Fn(&V) -> (f64, f64)) -> String {
+    let mut json = String::from(r#"{"type":"LineString","coordinates":["#);
+    for (i, &v) in path.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let (longitude, latitude) = coord_fn(v);
+        write!(json, "[{},{}]", longitude, latitude).unwrap();
+    }
+    json.push_str("]}");
+    json
+}
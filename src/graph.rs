@@ -0,0 +1,390 @@
+#[cfg(feature = "no_std")]
+use alloc::vec::Vec;
+use collections::{Key, Map};
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use dijkstra::{Dijkstra, Edge, Error, Vertex, Weight};
+
+/// Graph that owns its vertices and edges, with vertices identified by `Id`.
+///
+/// It is built up one vertex or edge at a time with `add_vertex` and `add_edge`, or through
+/// `GraphBuilder`, naming vertices by id so that no vertex needs to be borrowed while
+/// building. Edges are linked to their target vertices when the graph is first borrowed
+/// through `vertices`, `vertex` or `dijkstra`. From then on the graph stays borrowed and
+/// can no longer be modified.
+pub struct Graph<'a, Id, W> {
+    vertices: Vec<GraphVertex<'a, Id, W>>,
+    id_to_index_map: Map<Id, usize>,
+}
+
+pub struct GraphVertex<'a, Id, W> {
+    id: Id,
+    edges: Vec<GraphEdge<'a, Id, W>>,
+    // Vertex and edge indices of the edges arriving here, resolved through `graph`.
+    incoming: Vec<(usize, usize)>,
+    graph: Cell<Option<&'a [GraphVertex<'a, Id, W>]>>,
+}
+
+pub struct GraphEdge<'a, Id, W> {
+    to_index: usize,
+    to: Cell<Option<&'a GraphVertex<'a, Id, W>>>,
+    weight: W,
+}
+
+impl<'a, Id, W> Graph<'a, Id, W>
+where
+    Id: Key + Clone,
+    W: Weight,
+{
+    pub fn new() -> Self {
+        Graph {
+            vertices: vec![],
+            id_to_index_map: Map::new(),
+        }
+    }
+
+    pub fn builder() -> GraphBuilder<'a, Id, W> {
+        GraphBuilder {
+            graph: Graph::new(),
+        }
+    }
+
+    /// Builds a graph from `(from, to, weight)` edges, adding vertices as their ids first
+    /// appear. The edges are consumed one at a time, so they may be streamed from a file
+    /// or a cursor without collecting them first. Route through the result with
+    /// `dijkstra`, which borrows the vertices the graph owns.
+    pub fn from_edges(edges: impl IntoIterator<Item = (Id, Id, W)>) -> Self {
+        let mut graph = Graph::new();
+        for (from, to, weight) in edges {
+            graph.add_edge(from, to, weight);
+        }
+        graph
+    }
+
+    /// Like `from_edges`, but the vertices of `ids` are added first, in order, so vertices
+    /// without any edge are kept.
+    pub fn from_vertices_and_edges(
+        ids: impl IntoIterator<Item = Id>,
+        edges: impl IntoIterator<Item = (Id, Id, W)>,
+    ) -> Self {
+        let mut graph = Graph::new();
+        for id in ids {
+            graph.add_vertex(id);
+        }
+        for (from, to, weight) in edges {
+            graph.add_edge(from, to, weight);
+        }
+        graph
+    }
+
+    /// Like `from_edges`, but every edge weighs `W::one`, so routes are shortest in number
+    /// of edges.
+    pub fn from_edges_unweighted(edges: impl IntoIterator<Item = (Id, Id)>) -> Self {
+        Graph::from_edges(edges.into_iter().map(|(from, to)| (from, to, W::one())))
+    }
+
+    /// Builds a graph from an N×N matrix where `matrix[i][j]` is the weight of the edge from
+    /// `labels[i]` to `labels[j]`, and an infinite weight means no edge. The diagonal is
+    /// ignored, so matrices with zero there do not add self-loops. Fails with
+    /// `Error::MalformedMatrix` unless the matrix has one row and column per label.
+    pub fn from_adjacency_matrix(labels: Vec<Id>, matrix: Vec<Vec<W>>) -> Result<Self, Error> {
+        if matrix.len() != labels.len() || matrix.iter().any(|row| row.len() != labels.len()) {
+            return Err(Error::MalformedMatrix);
+        }
+
+        let mut graph = Graph::new();
+        for label in &labels {
+            graph.add_vertex(label.clone());
+        }
+        for (from, row) in matrix.into_iter().enumerate() {
+            for (to, weight) in row.into_iter().enumerate() {
+                if from != to && !weight.is_infinity() {
+                    graph.add_edge(labels[from].clone(), labels[to].clone(), weight);
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn add_vertex(&mut self, id: Id) -> usize {
+        if let Some(&index) = self.id_to_index_map.get(&id) {
+            return index;
+        }
+        let index = self.vertices.len();
+        self.id_to_index_map.insert(id.clone(), index);
+        self.vertices.push(GraphVertex {
+            id,
+            edges: vec![],
+            incoming: vec![],
+            graph: Cell::new(None),
+        });
+        index
+    }
+
+    pub fn add_edge(&mut self, from: Id, to: Id, weight: W) {
+        let from = self.add_vertex(from);
+        let to = self.add_vertex(to);
+        self.vertices[from].edges.push(GraphEdge {
+            to_index: to,
+            to: Cell::new(None),
+            weight,
+        });
+        let edge = self.vertices[from].edges.len() - 1;
+        self.vertices[to].incoming.push((from, edge));
+    }
+
+    /// Adds the vertices and edges of `other`. A vertex whose id exists in both graphs
+    /// becomes one vertex with the edges of both. Then an edge of `transfer_weight` is
+    /// added for each `(from, to)` pair of `transfers`.
+    pub fn merge(
+        &mut self,
+        other: Graph<'a, Id, W>,
+        transfers: impl IntoIterator<Item = (Id, Id)>,
+        transfer_weight: W,
+    ) {
+        let ids: Vec<_> = other.vertices.iter().map(|v| v.id.clone()).collect();
+        for id in &ids {
+            self.add_vertex(id.clone());
+        }
+        for vertex in other.vertices {
+            for edge in vertex.edges {
+                self.add_edge(vertex.id.clone(), ids[edge.to_index].clone(), edge.weight);
+            }
+        }
+
+        for (from, to) in transfers {
+            self.add_edge(from, to, transfer_weight.clone());
+        }
+    }
+
+    /// Every vertex id in insertion order, and every edge as `(from, to, weight)`, by
+    /// vertex and then by edge in insertion order, so `from_vertices_and_edges` rebuilds
+    /// the same graph, vertices without any edge included.
+    pub fn to_edge_list(&self) -> (Vec<Id>, Vec<(Id, Id, W)>) {
+        let ids = self
+            .vertices
+            .iter()
+            .map(|vertex| vertex.id.clone())
+            .collect();
+        let edges = self
+            .vertices
+            .iter()
+            .flat_map(|vertex| {
+                vertex.edges.iter().map(move |edge| {
+                    let to = self.vertices[edge.to_index].id.clone();
+                    (vertex.id.clone(), to, edge.weight.clone())
+                })
+            })
+            .collect();
+        (ids, edges)
+    }
+
+    /// Copy of the graph with every edge reversed, keeping the vertex order.
+    pub fn transpose(&self) -> Graph<'a, Id, W> {
+        let mut transposed = Graph::new();
+        for vertex in &self.vertices {
+            transposed.add_vertex(vertex.id.clone());
+        }
+        for vertex in &self.vertices {
+            for edge in &vertex.edges {
+                let to = self.vertices[edge.to_index].id.clone();
+                transposed.add_edge(to, vertex.id.clone(), edge.weight.clone());
+            }
+        }
+        transposed
+    }
+
+    /// Copy of the graph with every edge weight replaced by `f` of it, keeping the vertex
+    /// and edge order. Routes through the copy are found with its own `dijkstra`.
+    pub fn map_weights<'b, W2>(&self, f: impl Fn(&W) -> W2) -> Graph<'b, Id, W2>
+    where
+        W2: Weight,
+    {
+        let mut mapped = Graph::new();
+        for vertex in &self.vertices {
+            mapped.add_vertex(vertex.id.clone());
+        }
+        for vertex in &self.vertices {
+            for edge in &vertex.edges {
+                let to = self.vertices[edge.to_index].id.clone();
+                mapped.add_edge(vertex.id.clone(), to, f(&edge.weight));
+            }
+        }
+        mapped
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn vertices(&'a self) -> &'a [GraphVertex<'a, Id, W>] {
+        self.link();
+        &self.vertices
+    }
+
+    pub fn index_of(&self, id: &Id) -> Option<usize> {
+        self.id_to_index_map.get(id).copied()
+    }
+
+    pub fn vertex(&'a self, id: &Id) -> Option<&'a GraphVertex<'a, Id, W>> {
+        let index = self.index_of(id)?;
+        Some(&self.vertices()[index])
+    }
+
+    pub fn dijkstra(&'a self) -> Dijkstra<'a, GraphVertex<'a, Id, W>, GraphEdge<'a, Id, W>, W> {
+        Dijkstra::new(self.vertices())
+    }
+
+    fn link(&'a self) {
+        for vertex in &self.vertices {
+            vertex.graph.set(Some(&self.vertices));
+            for edge in &vertex.edges {
+                edge.to.set(Some(&self.vertices[edge.to_index]));
+            }
+        }
+    }
+}
+
+/// Builder for a `Graph`, adding vertices and edges by id in a chain and finishing with
+/// `build`. Route through the built graph with `Graph::dijkstra`.
+pub struct GraphBuilder<'a, Id, W> {
+    graph: Graph<'a, Id, W>,
+}
+
+impl<'a, Id, W> GraphBuilder<'a, Id, W>
+where
+    Id: Key + Clone,
+    W: Weight,
+{
+    pub fn add_vertex(mut self, id: Id) -> Self {
+        self.graph.add_vertex(id);
+        self
+    }
+
+    pub fn add_edge(mut self, from: Id, to: Id, weight: W) -> Self {
+        self.graph.add_edge(from, to, weight);
+        self
+    }
+
+    pub fn build(self) -> Graph<'a, Id, W> {
+        self.graph
+    }
+}
+
+impl<'a, Id, W> Default for Graph<'a, Id, W>
+where
+    Id: Key + Clone,
+    W: Weight,
+{
+    fn default() -> Self {
+        Graph::new()
+    }
+}
+
+impl<'a, Id, W> GraphVertex<'a, Id, W> {
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+}
+
+impl<'a, Id, W> Vertex<'a, GraphEdge<'a, Id, W>, W> for GraphVertex<'a, Id, W>
+where
+    Id: Key + 'a,
+    W: Weight + 'a,
+{
+    type Edges = core::slice::Iter<'a, GraphEdge<'a, Id, W>>;
+    fn edges(&'a self) -> Self::Edges {
+        self.edges.iter()
+    }
+
+    fn incoming_edges(&'a self, _: &[&'a Self]) -> Vec<(&'a Self, &'a GraphEdge<'a, Id, W>)> {
+        let vertices = self
+            .graph
+            .get()
+            .expect("edges are only reachable from a linked graph");
+        self.incoming
+            .iter()
+            .map(|&(from, edge)| (&vertices[from], &vertices[from].edges[edge]))
+            .collect()
+    }
+}
+
+impl<'a, Id, W> Edge<'a, GraphVertex<'a, Id, W>, W> for GraphEdge<'a, Id, W>
+where
+    Id: Key + 'a,
+    W: Weight + 'a,
+{
+    fn get_to(&'a self) -> &'a GraphVertex<'a, Id, W> {
+        self.to
+            .get()
+            .expect("edges are only reachable from a linked graph")
+    }
+
+    fn get_weight(&self) -> &W {
+        &self.weight
+    }
+}
+
+impl<'a, Id, W> Hash for GraphVertex<'a, Id, W>
+where
+    Id: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<'a, Id, W> PartialEq for GraphVertex<'a, Id, W>
+where
+    Id: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.id.eq(&other.id)
+    }
+}
+
+impl<'a, Id, W> Eq for GraphVertex<'a, Id, W> where Id: Eq {}
+
+impl<'a, Id, W> PartialOrd for GraphVertex<'a, Id, W>
+where
+    Id: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, Id, W> Ord for GraphVertex<'a, Id, W>
+where
+    Id: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl<'a, Id, W> fmt::Debug for GraphVertex<'a, Id, W>
+where
+    Id: fmt::Debug,
+    W: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} {:?}", self.id, self.edges)
+    }
+}
+
+impl<'a, Id, W> fmt::Debug for GraphEdge<'a, Id, W>
+where
+    W: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "-{:?}> #{}", self.weight, self.to_index)
+    }
+}
@@ -1 +1,17 @@
+#![cfg_attr(feature = "no_std", no_std)]
+
+#[cfg(feature = "no_std")]
+#[macro_use]
+extern crate alloc;
+#[cfg(not(feature = "no_std"))]
+extern crate core;
+
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod collections;
+pub mod csv;
 pub mod dijkstra;
+pub mod geojson;
+pub mod graph;
+pub mod reachability;
+pub mod weight;
@@ -0,0 +1,71 @@
+#[cfg(feature = "no_std")]
+use alloc::vec::Vec;
+use collections::{Key, Map, Set, VecDeque};
+
+/// Every vertex reachable from `starts`, the starts included, in breadth-first order.
+/// The graph is given only by `neighbors`, which lists the vertices one edge away, such as
+/// `|v| v.edges().into_iter().map(|edge| edge.get_to())` for a `Vertex`, so no `Weight` is
+/// involved.
+pub fn bfs_reachable<V, I>(
+    starts: impl IntoIterator<Item = V>,
+    neighbors: impl Fn(&V) -> I,
+) -> Vec<V>
+where
+    V: Key + Clone,
+    I: IntoIterator<Item = V>,
+{
+    let mut seen = Set::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if seen.insert(start.clone()) {
+            queue.push_back(start);
+        }
+    }
+
+    let mut order = vec![];
+    while let Some(now) = queue.pop_front() {
+        for next in neighbors(&now) {
+            if seen.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+        order.push(now);
+    }
+    order
+}
+
+/// Route from `start` to `end` with the fewest edges, with its number of edges, on a graph
+/// given by `neighbors` as for `bfs_reachable`.
+pub fn bfs_shortest_hops<V, I>(
+    start: V,
+    end: V,
+    neighbors: impl Fn(&V) -> I,
+) -> Option<(Vec<V>, usize)>
+where
+    V: Key + Clone,
+    I: IntoIterator<Item = V>,
+{
+    let mut predecessors = Map::<V, Option<V>>::new();
+    let mut queue = VecDeque::new();
+    predecessors.insert(start.clone(), None);
+    queue.push_back(start);
+
+    while let Some(now) = queue.pop_front() {
+        if now == end {
+            let mut route = vec![now];
+            while let Some(Some(prev)) = predecessors.get(&route[route.len() - 1]) {
+                route.push(prev.clone());
+            }
+            route.reverse();
+            let hops = route.len() - 1;
+            return Some((route, hops));
+        }
+        for next in neighbors(&now) {
+            if !predecessors.contains_key(&next) {
+                predecessors.insert(next.clone(), Some(now.clone()));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,374 @@
+use core::cmp::Ordering;
+use core::f64;
+use core::ops::Add;
+
+use dijkstra::{BucketWeight, Weight, WideWeight};
+
+/// `f64` weight ordered by `f64::total_cmp`, so it is safe to use in the search heap.
+/// `NaN` is rejected on construction, and sums that would be `NaN` (such as infinity
+/// plus negative infinity) become infinity.
+#[derive(Debug, Clone, Copy)]
+pub struct FloatWeight(f64);
+
+impl FloatWeight {
+    pub fn new(value: f64) -> Option<Self> {
+        if value.is_nan() {
+            None
+        } else {
+            Some(FloatWeight(value))
+        }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+impl PartialEq for FloatWeight {
+    fn eq(&self, other: &FloatWeight) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FloatWeight {}
+
+impl PartialOrd for FloatWeight {
+    fn partial_cmp(&self, other: &FloatWeight) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloatWeight {
+    fn cmp(&self, other: &FloatWeight) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Weight for FloatWeight {
+    fn add(&self, other: &Self) -> Self {
+        let sum = self.0 + other.0;
+        if sum.is_nan() {
+            Self::infinity()
+        } else {
+            FloatWeight(sum)
+        }
+    }
+
+    fn zero() -> Self {
+        FloatWeight(0.0)
+    }
+
+    fn one() -> Self {
+        FloatWeight(1.0)
+    }
+
+    fn infinity() -> Self {
+        FloatWeight(f64::INFINITY)
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.0 == f64::INFINITY
+    }
+
+    fn scale(&self, factor: u32) -> Self {
+        FloatWeight(self.0.scale(factor))
+    }
+}
+
+impl From<FloatWeight> for f64 {
+    fn from(weight: FloatWeight) -> f64 {
+        weight.0
+    }
+}
+
+impl BucketWeight for FloatWeight {
+    fn bucket(&self, bucket_size: &Self) -> Option<usize> {
+        if bucket_size.0 > 0.0 {
+            // Saturates to `usize::MAX` for quotients too large.
+            Some((self.0 / bucket_size.0) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// Plain `f64` weights, ordered by `f64::total_cmp` in the search. As with `FloatWeight`,
+/// sums that would be `NaN` become infinity, so an edge weighing `NaN` is never taken.
+impl Weight for f64 {
+    fn add(&self, other: &Self) -> Self {
+        let sum = self + other;
+        if sum.is_nan() {
+            f64::INFINITY
+        } else {
+            sum
+        }
+    }
+
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn one() -> Self {
+        1.0
+    }
+
+    fn infinity() -> Self {
+        f64::INFINITY
+    }
+
+    fn is_infinity(&self) -> bool {
+        *self == f64::INFINITY
+    }
+
+    fn scale(&self, factor: u32) -> Self {
+        let product = self * factor as f64;
+        if product.is_nan() {
+            f64::INFINITY
+        } else {
+            product
+        }
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+/// Weight that can be multiplied by a fractional factor, for converting it to another
+/// unit. An infinite weight stays infinite, even for a factor of zero.
+pub trait Scale
+where
+    Self: Weight,
+{
+    /// This weight times `factor`, which is finite and not negative.
+    fn scale_by(&self, factor: f64) -> Self;
+}
+
+impl Scale for f64 {
+    fn scale_by(&self, factor: f64) -> Self {
+        let product = self * factor;
+        if product.is_nan() {
+            f64::INFINITY
+        } else {
+            product
+        }
+    }
+}
+
+impl Scale for FloatWeight {
+    fn scale_by(&self, factor: f64) -> Self {
+        FloatWeight(self.0.scale_by(factor))
+    }
+}
+
+/// Weight `W` converted to another unit by a factor given at run time, such as meters to
+/// minutes on a graph weighted in meters. The factor is applied once, when the weight is
+/// made with `new`, so searches add and compare weights that are already in the new unit,
+/// and every other `Weight` method is that of `W`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScaledWeight<W>(W);
+
+impl<W> ScaledWeight<W>
+where
+    W: Scale,
+{
+    /// `weight` times `factor`, or `None` when `factor` is negative, infinite or `NaN`.
+    /// An infinite weight stays infinite.
+    pub fn new(weight: &W, factor: f64) -> Option<Self> {
+        if factor.is_finite() && factor >= 0.0 {
+            Some(ScaledWeight(weight.scale_by(factor)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<W> ScaledWeight<W> {
+    /// The weight in the new unit.
+    pub fn value(&self) -> &W {
+        &self.0
+    }
+}
+
+impl<W> Weight for ScaledWeight<W>
+where
+    W: Weight,
+{
+    fn add(&self, other: &Self) -> Self {
+        ScaledWeight(self.0.add(&other.0))
+    }
+
+    fn zero() -> Self {
+        ScaledWeight(W::zero())
+    }
+
+    fn one() -> Self {
+        ScaledWeight(W::one())
+    }
+
+    fn infinity() -> Self {
+        ScaledWeight(W::infinity())
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.0.is_infinity()
+    }
+
+    fn is_max(&self) -> bool {
+        self.0.is_max()
+    }
+
+    fn scale(&self, factor: u32) -> Self {
+        ScaledWeight(self.0.scale(factor))
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl<W> Scale for ScaledWeight<W>
+where
+    W: Scale,
+{
+    fn scale_by(&self, factor: f64) -> Self {
+        ScaledWeight(self.0.scale_by(factor))
+    }
+}
+
+// Unsigned integers saturate at their maximum, which stands for infinity.
+macro_rules! integer_weight {
+    ($($integer:ty),*) => {
+        $(
+            impl Weight for $integer {
+                fn add(&self, other: &Self) -> Self {
+                    self.saturating_add(*other)
+                }
+
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                fn infinity() -> Self {
+                    <$integer>::MAX
+                }
+
+                fn is_infinity(&self) -> bool {
+                    *self == <$integer>::MAX
+                }
+
+                fn scale(&self, factor: u32) -> Self {
+                    if self.is_infinity() {
+                        return Self::infinity();
+                    }
+                    (*self as u128)
+                        .saturating_mul(factor as u128)
+                        .min(<$integer>::MAX as u128) as $integer
+                }
+            }
+
+            // Rounds to the nearest integer, saturating to infinity.
+            impl Scale for $integer {
+                fn scale_by(&self, factor: f64) -> Self {
+                    let product = *self as f64 * factor;
+                    if self.is_infinity() || product >= <$integer>::MAX as f64 {
+                        return Self::infinity();
+                    }
+                    (product + 0.5) as $integer
+                }
+            }
+        )*
+    };
+}
+
+integer_weight!(u8, u16, u32, u64, u128, usize);
+
+// Narrower unsigned integers widen to `u128`, keeping their maximum as infinity.
+macro_rules! wide_integer_weight {
+    ($($integer:ty),*) => {
+        $(
+            impl WideWeight for $integer {
+                type Wide = u128;
+
+                fn widen(&self) -> u128 {
+                    if self.is_infinity() {
+                        u128::MAX
+                    } else {
+                        *self as u128
+                    }
+                }
+
+                fn narrow(wide: &u128) -> Self {
+                    if *wide >= <$integer>::MAX as u128 {
+                        Self::infinity()
+                    } else {
+                        *wide as $integer
+                    }
+                }
+            }
+        )*
+    };
+}
+
+wide_integer_weight!(u8, u16, u32, u64, usize);
+
+/// Types with a largest value, which `OpsWeight` takes for infinity.
+pub trait Bounded {
+    fn max_value() -> Self;
+}
+
+macro_rules! bounded {
+    ($($integer:ty),*) => {
+        $(
+            impl Bounded for $integer {
+                fn max_value() -> Self {
+                    <$integer>::MAX
+                }
+            }
+        )*
+    };
+}
+
+bounded!(u8, u16, u32, u64, u128, usize, i16, i32, i64, i128, isize);
+
+/// Weight for any type that already has `Add`, `Ord`, `Default` as zero, `From<u8>` for
+/// the unit and `Bounded`, so it need not implement `Weight` itself.
+///
+/// `max_value` stands for infinity, and a sum with an infinite operand is infinite.
+/// Other sums are left to `T::add`, so one that overflows `T` does whatever `T` does on
+/// overflow, such as panicking for primitive integers in debug builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OpsWeight<T>(pub T);
+
+impl<T> Weight for OpsWeight<T>
+where
+    T: Add<Output = T> + Ord + Default + Bounded + From<u8> + Clone,
+{
+    fn add(&self, other: &Self) -> Self {
+        if self.is_infinity() || other.is_infinity() {
+            Self::infinity()
+        } else {
+            OpsWeight(self.0.clone() + other.0.clone())
+        }
+    }
+
+    fn zero() -> Self {
+        OpsWeight(T::default())
+    }
+
+    fn one() -> Self {
+        OpsWeight(T::from(1))
+    }
+
+    fn infinity() -> Self {
+        OpsWeight(T::max_value())
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.0 == T::max_value()
+    }
+}
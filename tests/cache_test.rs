@@ -0,0 +1,83 @@
+#![cfg(feature = "cache")]
+
+extern crate subway;
+
+use subway::cache::CachedDijkstra;
+use subway::graph::*;
+
+#[test]
+fn cache_hit_test() {
+    let graph = Graph::from_edges(vec![("S", "C", 3u32), ("C", "D", 12), ("S", "D", 20)]);
+    let s = graph.vertex(&"S").unwrap();
+    let c = graph.vertex(&"C").unwrap();
+    let d = graph.vertex(&"D").unwrap();
+
+    let mut cached = CachedDijkstra::new(graph.dijkstra(), 2);
+    let route = cached.shortest_path(s, d);
+    assert_eq!(route, Some((vec![s, c, d], 15)));
+    assert_eq!((cached.hits(), cached.misses()), (0, 1));
+
+    assert_eq!(cached.shortest_path(s, d), route);
+    assert_eq!((cached.hits(), cached.misses()), (1, 1));
+
+    // S-C pushes out nothing; S-D is then the most recent, so C-S pushes out S-C.
+    assert_eq!(cached.shortest_path(s, c), Some((vec![s, c], 3)));
+    assert_eq!(cached.shortest_path(s, d), route);
+    assert_eq!(cached.shortest_path(c, s), None);
+    assert_eq!(cached.len(), 2);
+    assert_eq!((cached.hits(), cached.misses()), (2, 3));
+
+    cached.shortest_path(s, c);
+    assert_eq!((cached.hits(), cached.misses()), (2, 4));
+
+    cached.set_capacity(0);
+    assert!(cached.is_empty());
+    cached.shortest_path(s, d);
+    assert_eq!((cached.hits(), cached.misses()), (2, 5));
+}
+
+#[test]
+fn cache_invalidation_test() {
+    let direct = Graph::from_edges(vec![("S", "D", 20u32)]);
+    // Same indices for S and D, plus a shortcut through C.
+    let detour = Graph::from_edges(vec![("S", "D", 20u32), ("S", "C", 3), ("C", "D", 12)]);
+
+    let mut cached = CachedDijkstra::new(direct.dijkstra(), 8);
+    let (s, d) = (direct.vertex(&"S").unwrap(), direct.vertex(&"D").unwrap());
+    assert_eq!(cached.shortest_path(s, d), Some((vec![s, d], 20)));
+
+    cached.rebuild(detour.vertices());
+    assert!(cached.is_empty());
+    let s = detour.vertex(&"S").unwrap();
+    let c = detour.vertex(&"C").unwrap();
+    let d = detour.vertex(&"D").unwrap();
+    assert_eq!(cached.shortest_path(s, d), Some((vec![s, c, d], 15)));
+    assert_eq!((cached.hits(), cached.misses()), (0, 2));
+
+    cached.set_hop_tiebreak(true);
+    assert!(cached.is_empty());
+}
+
+#[test]
+fn cache_shrink_test() {
+    let graph = Graph::from_edges(vec![("S", "C", 3u32), ("C", "D", 12), ("S", "D", 20)]);
+    let s = graph.vertex(&"S").unwrap();
+    let c = graph.vertex(&"C").unwrap();
+    let d = graph.vertex(&"D").unwrap();
+
+    let mut cached = CachedDijkstra::new(graph.dijkstra(), 3);
+    cached.shortest_path(s, c);
+    cached.shortest_path(s, d);
+    cached.shortest_path(c, d);
+    // S-C becomes the most recent, so shrinking forgets S-D and then C-D.
+    cached.shortest_path(s, c);
+    cached.set_capacity(1);
+    assert_eq!(cached.len(), 1);
+    assert_eq!((cached.hits(), cached.misses()), (1, 3));
+
+    cached.shortest_path(s, c);
+    assert_eq!((cached.hits(), cached.misses()), (2, 3));
+    cached.shortest_path(c, d);
+    cached.shortest_path(s, d);
+    assert_eq!((cached.hits(), cached.misses()), (2, 5));
+}
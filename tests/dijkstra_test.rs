@@ -1,8 +1,12 @@
 extern crate subway;
 
+use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::fmt;
-use std::hash::Hash;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::iter;
+use subway::collections::{Map, Set};
 use subway::dijkstra::*;
 
 #[derive(Clone)]
@@ -56,6 +60,13 @@ impl Weight for SimpleWeight {
         }
     }
 
+    fn one() -> Self {
+        SimpleWeight {
+            weight: 1,
+            is_infinity: false,
+        }
+    }
+
     fn infinity() -> Self {
         SimpleWeight {
             weight: 0,
@@ -64,6 +75,18 @@ impl Weight for SimpleWeight {
     }
 }
 
+impl BucketWeight for SimpleWeight {
+    fn bucket(&self, bucket_size: &Self) -> Option<usize> {
+        self.weight.checked_div(bucket_size.weight)
+    }
+}
+
+impl From<SimpleWeight> for f64 {
+    fn from(weight: SimpleWeight) -> f64 {
+        weight.weight as f64
+    }
+}
+
 impl fmt::Debug for SimpleWeight {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -81,16 +104,21 @@ impl fmt::Debug for SimpleWeight {
 struct SimpleEdge<'a> {
     to: &'a SimpleVertex<'a>,
     weight: SimpleWeight,
+    id: Option<EdgeId>,
 }
 
 impl<'a> Edge<'a, SimpleVertex<'a>, SimpleWeight> for SimpleEdge<'a> {
-    fn get_to(&'a self) -> &'a SimpleVertex {
-        &self.to
+    fn get_to(&'a self) -> &'a SimpleVertex<'a> {
+        self.to
     }
 
     fn get_weight(&self) -> &SimpleWeight {
         &self.weight
     }
+
+    fn id(&self) -> Option<EdgeId> {
+        self.id
+    }
 }
 
 impl<'a> fmt::Debug for SimpleEdge<'a> {
@@ -118,6 +146,24 @@ impl<'a> PartialEq for SimpleVertex<'a> {
 
 impl<'a> Eq for SimpleVertex<'a> {}
 
+impl<'a> PartialOrd for SimpleVertex<'a> {
+    fn partial_cmp(&self, other: &SimpleVertex) -> Option<Ordering> {
+        Some(Ord::cmp(self, other))
+    }
+}
+
+impl<'a> Ord for SimpleVertex<'a> {
+    fn cmp(&self, other: &SimpleVertex) -> Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+impl<'a> Borrow<str> for SimpleVertex<'a> {
+    fn borrow(&self) -> &str {
+        &self.name
+    }
+}
+
 impl<'a> Vertex<'a, SimpleEdge<'a>, SimpleWeight> for SimpleVertex<'a> {
     type Edges = std::slice::Iter<'a, SimpleEdge<'a>>;
     fn edges(&'a self) -> Self::Edges {
@@ -146,8 +192,35 @@ impl<'a> SimpleVertex<'a> {
                 weight,
                 is_infinity: false,
             },
+            id: None,
         });
     }
+
+    fn add_edge_with_id(&mut self, to: &'a SimpleVertex, weight: usize, id: EdgeId) {
+        self.add_edge(to, weight);
+        self.edges.last_mut().unwrap().id = Some(id);
+    }
+}
+
+fn simple(weight: usize) -> SimpleWeight {
+    SimpleWeight {
+        weight,
+        is_infinity: false,
+    }
+}
+
+macro_rules! sample_graph {
+    ($s:ident, $b:ident, $c:ident, $d:ident) => {
+        let $d = SimpleVertex::new("D".to_owned());
+        let mut $c = SimpleVertex::new("C".to_owned());
+        let $b = SimpleVertex::new("B".to_owned());
+        let mut $s = SimpleVertex::new("S".to_owned());
+
+        $c.add_edge(&$d, 12);
+        $s.add_edge(&$b, 24);
+        $s.add_edge(&$c, 3);
+        $s.add_edge(&$d, 20);
+    };
 }
 
 #[test]
@@ -178,3 +251,1779 @@ fn dijkstra_basic_test() {
     assert_eq!(to_d.0, vec![&s, &c, &d]);
     assert_eq!(to_d.1.weight, 15);
 }
+
+#[test]
+fn dijkstra_max_iterations_test() {
+    let leaves: Vec<_> = (0..1000)
+        .map(|i| SimpleVertex::new(format!("L{}", i)))
+        .collect();
+    let mut s = SimpleVertex::new("S".to_owned());
+    for (i, leaf) in leaves.iter().enumerate() {
+        s.add_edge(leaf, i + 1);
+    }
+
+    let list = iter::once(&s).chain(leaves.iter());
+    let dijkstra = Dijkstra::new(list);
+
+    let far = &leaves[leaves.len() - 1];
+
+    let limited = dijkstra.find_shortest_path_limited(vec![&s], vec![far], 10);
+    assert_eq!(limited.unwrap_err(), Error::Timeout);
+
+    let unlimited = dijkstra
+        .find_shortest_path_limited(vec![&s], vec![far], 2000)
+        .unwrap();
+    assert_eq!(unlimited.0, vec![&s, far]);
+    assert_eq!(unlimited.1.weight, 1000);
+}
+
+#[test]
+fn backtrack_corrupted_test() {
+    assert_eq!(
+        backtrack(&[Some(0), Some(0), Some(1)], 2),
+        Ok(vec![0, 1, 2])
+    );
+
+    assert_eq!(
+        backtrack(&[Some(0), None, Some(1)], 2),
+        Err(Error::CorruptedBacktrack)
+    );
+    assert_eq!(
+        backtrack(&[Some(0), Some(2), Some(1)], 2),
+        Err(Error::CorruptedBacktrack)
+    );
+    assert_eq!(
+        backtrack(&[Some(0), Some(5), Some(1)], 2),
+        Err(Error::CorruptedBacktrack)
+    );
+}
+
+#[test]
+fn backtrack_end_first_test() {
+    let backtracker = [Some(0), Some(0), Some(1)];
+    assert_eq!(backtrack_end_first(&backtracker, 2), Ok(vec![2, 1, 0]));
+    assert_eq!(
+        backtrack_end_first(&[Some(0), None, Some(1)], 2),
+        Err(Error::CorruptedBacktrack)
+    );
+
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    for end in [&s, &b, &c, &d] {
+        let (mut route, weight) = dijkstra.shortest_path_end_first(&s, end).unwrap();
+        route.reverse();
+        assert_eq!(Some((route, weight)), dijkstra.shortest_path(&s, end));
+    }
+    assert_eq!(
+        dijkstra.shortest_path_end_first(&s, &d),
+        Some((vec![&d, &c, &s], simple(15)))
+    );
+    assert_eq!(dijkstra.shortest_path_end_first(&b, &s), None);
+}
+
+#[test]
+fn shortest_path_dag_edges_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+
+    b.add_edge(&d, 2);
+    c.add_edge(&d, 1);
+    a.add_edge(&b, 1);
+    a.add_edge(&c, 2);
+    a.add_edge(&d, 5);
+
+    let dijkstra = Dijkstra::new(vec![&a, &b, &c, &d]);
+
+    let dag: Vec<_> = dijkstra
+        .shortest_path_dag_edges(&a)
+        .into_iter()
+        .map(|(from, to, weight)| (from.name.as_str(), to.name.as_str(), weight.weight))
+        .collect();
+    assert_eq!(
+        dag,
+        vec![("A", "B", 1), ("A", "C", 2), ("B", "D", 2), ("C", "D", 1)]
+    );
+}
+
+#[test]
+fn shortest_path_dag_blocked_edge_test() {
+    let x = SimpleVertex::new("X".to_owned());
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+
+    a.add_edge(&d, 3);
+    a.add_edge(&x, 0);
+    a.edges[1].weight = SimpleWeight::infinity();
+
+    let dijkstra = Dijkstra::new(vec![&a, &d, &x]);
+
+    let dag: Vec<_> = dijkstra
+        .shortest_path_dag_edges(&a)
+        .into_iter()
+        .map(|(from, to, _)| (from.name.as_str(), to.name.as_str()))
+        .collect();
+    assert_eq!(dag, vec![("A", "D")]);
+}
+
+#[test]
+fn eccentricity_diameter_test() {
+    sample_graph!(s, b, c, d);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    assert_eq!(dijkstra.eccentricity(&s).unwrap().weight, 24);
+    assert_eq!(dijkstra.eccentricity(&b).unwrap().weight, 0);
+    assert_eq!(dijkstra.eccentricity(&c).unwrap().weight, 12);
+    assert_eq!(dijkstra.eccentricity(&d).unwrap().weight, 0);
+    assert_eq!(dijkstra.diameter().unwrap().weight, 24);
+
+    let outsider = SimpleVertex::new("X".to_owned());
+    assert!(dijkstra.eccentricity(&outsider).is_none());
+}
+
+#[test]
+fn distance_matrix_test() {
+    sample_graph!(s, b, c, d);
+
+    let list = vec![&s, &b, &c, &d];
+    let dijkstra = Dijkstra::new(list.clone());
+
+    let pairs: Vec<_> = list
+        .iter()
+        .flat_map(|&from| list.iter().map(move |&to| (from, to)))
+        .collect();
+    let matrix = dijkstra.distance_matrix(&pairs);
+
+    for (&(from, to), distance) in pairs.iter().zip(&matrix) {
+        assert_eq!(*distance, dijkstra.shortest_distance(from, to));
+    }
+    assert_eq!(matrix[3].as_ref().map(|w| w.weight), Some(15));
+    assert_eq!(matrix[4], None);
+}
+
+#[test]
+fn vertex_costs_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    a.add_edge(&d, 1);
+    b.add_edge(&d, 2);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 2);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &d]);
+
+    let cost = |v: &SimpleVertex| SimpleWeight {
+        weight: match v.name.as_str() {
+            "S" => 5,
+            "A" => 10,
+            _ => 0,
+        },
+        is_infinity: false,
+    };
+
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(route, vec![&s, &a, &d]);
+    assert_eq!(weight.weight, 2);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_vertex_costs(vec![&s], vec![&d], cost, false)
+        .unwrap();
+    assert_eq!(route, vec![&s, &b, &d]);
+    assert_eq!(weight.weight, 4);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_vertex_costs(vec![&s], vec![&d], cost, true)
+        .unwrap();
+    assert_eq!(route, vec![&s, &b, &d]);
+    assert_eq!(weight.weight, 9);
+}
+
+#[test]
+fn vertices_order_test() {
+    sample_graph!(s, b, c, d);
+
+    let list = vec![&d, &s, &c, &b];
+    let dijkstra = Dijkstra::new(list.clone());
+
+    assert_eq!(dijkstra.vertices(), &list[..]);
+    assert_eq!(dijkstra.into_vertices(), list);
+}
+
+#[test]
+fn alternatives_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    a.add_edge(&d, 1);
+    b.add_edge(&d, 2);
+    c.add_edge(&d, 3);
+    b.add_edge(&a, 1);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 2);
+    s.add_edge(&c, 3);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &c, &d]);
+
+    let alternatives = dijkstra.alternatives(&s, &d, 3, 0.5);
+    assert_eq!(alternatives.len(), 3);
+    assert_eq!(alternatives[0].0, vec![&s, &a, &d]);
+    assert_eq!(alternatives[0].1.weight, 2);
+
+    let edges = |route: &[&SimpleVertex]| -> Vec<(String, String)> {
+        route
+            .windows(2)
+            .map(|pair| (pair[0].name.clone(), pair[1].name.clone()))
+            .collect()
+    };
+    for (i, (x, _)) in alternatives.iter().enumerate() {
+        for (y, _) in &alternatives[i + 1..] {
+            let (x, y) = (edges(x), edges(y));
+            let shared = x.iter().filter(|edge| y.contains(edge)).count();
+            assert!((shared as f64) / (x.len() as f64) < 0.5);
+            assert!((shared as f64) / (y.len() as f64) < 0.5);
+        }
+    }
+}
+
+#[test]
+fn hop_tiebreak_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    b.add_edge(&d, 1);
+    c.add_edge(&d, 1);
+    a.add_edge(&b, 1);
+    s.add_edge(&a, 1);
+    s.add_edge(&c, 2);
+
+    let mut dijkstra = Dijkstra::new(vec![&s, &a, &b, &c, &d]);
+
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 3);
+
+    dijkstra.set_hop_tiebreak(false);
+    let (_, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(weight.weight, 3);
+}
+
+#[test]
+fn distance_histogram_test() {
+    sample_graph!(s, b, c, d);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let bucket_size = SimpleWeight {
+        weight: 10,
+        is_infinity: false,
+    };
+    assert_eq!(
+        dijkstra.distance_histogram(&s, &bucket_size),
+        Ok(vec![2, 1, 1])
+    );
+    assert_eq!(
+        dijkstra.distance_histogram(&c, &bucket_size),
+        Ok(vec![1, 1])
+    );
+    assert_eq!(
+        dijkstra.distance_histogram(&s, &simple(0)),
+        Err(Error::InvalidBucketSize)
+    );
+}
+
+#[test]
+fn trivial_ends_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    a.add_edge(&d, 2);
+    s.add_edge(&a, 5);
+    s.add_edge(&d, 10);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &d]);
+
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s, &a], vec![&a, &d]);
+    assert_eq!(route, vec![&a]);
+    assert_eq!(weight.weight, 0);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_trivial_ends(vec![&s, &a], vec![&a, &d], true)
+        .unwrap();
+    assert_eq!(route, vec![&a]);
+    assert_eq!(weight.weight, 0);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_trivial_ends(vec![&s, &a], vec![&a, &d], false)
+        .unwrap();
+    assert_eq!(route, vec![&a, &d]);
+    assert_eq!(weight.weight, 2);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_trivial_ends(vec![&s, &a], vec![&a], false)
+        .unwrap();
+    assert_eq!(route, vec![&s, &a]);
+    assert_eq!(weight.weight, 5);
+
+    assert!(dijkstra
+        .find_shortest_path_with_trivial_ends(vec![&s], vec![&s], false)
+        .is_none());
+}
+
+#[test]
+fn trivial_ends_blocked_edge_test() {
+    let a = SimpleVertex::new("A".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    s.add_edge(&a, 0);
+    s.edges[0].weight = SimpleWeight::infinity();
+
+    let dijkstra = Dijkstra::new(vec![&s, &a]);
+    assert!(dijkstra
+        .find_shortest_path_with_trivial_ends(vec![&s, &a], vec![&a], false)
+        .is_none());
+}
+
+struct ShortcutEdge<'a> {
+    name: &'static str,
+    via: Option<(&'a ShortcutEdge<'a>, &'a ShortcutEdge<'a>)>,
+}
+
+impl<'a> Shortcut for ShortcutEdge<'a> {
+    fn shortcut_of(&self) -> Option<(&Self, &Self)> {
+        self.via
+    }
+}
+
+#[test]
+fn unpack_shortcut_test() {
+    let original = |name| ShortcutEdge { name, via: None };
+    let (ab, bc, cd) = (original("AB"), original("BC"), original("CD"));
+
+    let ac = ShortcutEdge {
+        name: "AC",
+        via: Some((&ab, &bc)),
+    };
+    let ad = ShortcutEdge {
+        name: "AD",
+        via: Some((&ac, &cd)),
+    };
+
+    let names: Vec<_> = unpack_shortcut(&ad).iter().map(|e| e.name).collect();
+    assert_eq!(names, vec!["AB", "BC", "CD"]);
+
+    let names: Vec<_> = unpack_shortcut(&ab).iter().map(|e| e.name).collect();
+    assert_eq!(names, vec!["AB"]);
+}
+
+#[test]
+fn edges_between_test() {
+    let c = SimpleVertex::new("C".to_owned());
+    let b = SimpleVertex::new("B".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+
+    a.add_edge(&b, 3);
+    a.add_edge(&c, 1);
+    a.add_edge(&b, 5);
+
+    let dijkstra = Dijkstra::new(vec![&a, &b, &c]);
+
+    let weights: Vec<_> = dijkstra
+        .edges_between(&a, &b)
+        .iter()
+        .map(|edge| edge.get_weight().weight)
+        .collect();
+    assert_eq!(weights, vec![3, 5]);
+    assert!(dijkstra.edges_between(&b, &a).is_empty());
+}
+
+#[test]
+fn distances_to_ends_test() {
+    sample_graph!(s, b, c, d);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let distances = dijkstra.distances_to_ends(vec![&s], vec![&c, &d]);
+    assert_eq!(distances.len(), 2);
+    assert_eq!(distances[&c].weight, 3);
+    assert_eq!(distances[&d].weight, 15);
+
+    let distances = dijkstra.distances_to_ends(vec![&c], vec![&b, &d]);
+    assert_eq!(distances.len(), 1);
+    assert_eq!(distances[&d].weight, 12);
+}
+
+#[test]
+fn resume_search_test() {
+    sample_graph!(s, b, c, d);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let mut search = dijkstra.begin_search(vec![&s]);
+
+    let (route, weight) = dijkstra.resume_search(&mut search, vec![&c]).unwrap();
+    assert_eq!(route, vec![&s, &c]);
+    assert_eq!(weight.weight, 3);
+
+    let resumed = dijkstra.resume_search(&mut search, vec![&b]).unwrap();
+    assert_eq!(resumed, dijkstra.find_shorted_path(vec![&s], vec![&b]));
+
+    let resumed = dijkstra.resume_search(&mut search, vec![&d]).unwrap();
+    assert_eq!(resumed, dijkstra.find_shorted_path(vec![&s], vec![&d]));
+
+    let (route, weight) = dijkstra.resume_search(&mut search, vec![&c]).unwrap();
+    assert_eq!(route, vec![&s, &c]);
+    assert_eq!(weight.weight, 3);
+}
+
+#[test]
+fn minimum_spanning_tree_test() {
+    sample_graph!(s, b, c, d);
+    let f = SimpleVertex::new("F".to_owned());
+    let mut e = SimpleVertex::new("E".to_owned());
+    e.add_edge(&f, 7);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d, &e, &f]);
+
+    let tree: Vec<_> = dijkstra
+        .minimum_spanning_tree()
+        .into_iter()
+        .map(|(from, to, weight)| (from.name.as_str(), to.name.as_str(), weight.weight))
+        .collect();
+    assert_eq!(
+        tree,
+        vec![("S", "C", 3), ("C", "D", 12), ("S", "B", 24), ("E", "F", 7)]
+    );
+}
+
+#[test]
+fn spanning_tree_blocked_edge_test() {
+    let b = SimpleVertex::new("B".to_owned());
+    let c = SimpleVertex::new("C".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    a.add_edge(&b, 1);
+    a.add_edge(&c, 2);
+    a.edges[0].weight = SimpleWeight::infinity();
+
+    let dijkstra = Dijkstra::new(vec![&a, &b, &c]);
+    let tree: Vec<_> = dijkstra
+        .minimum_spanning_tree()
+        .into_iter()
+        .map(|(from, to, weight)| (from.name.as_str(), to.name.as_str(), weight.weight))
+        .collect();
+    assert_eq!(tree, vec![("A", "C", 2)]);
+}
+
+#[test]
+fn find_shortest_path_within_test() {
+    sample_graph!(s, b, c, d);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let allowed: Set<_> = vec![&s, &b, &c, &d].into_iter().collect();
+    let (route, weight) = dijkstra
+        .find_shortest_path_within(&allowed, vec![&s], vec![&d])
+        .unwrap();
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 15);
+
+    let allowed: Set<_> = vec![&s, &b, &d].into_iter().collect();
+    let (route, weight) = dijkstra
+        .find_shortest_path_within(&allowed, vec![&s], vec![&d])
+        .unwrap();
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight.weight, 20);
+
+    let allowed: Set<_> = vec![&s, &b, &c].into_iter().collect();
+    assert!(dijkstra
+        .find_shortest_path_within(&allowed, vec![&s], vec![&d])
+        .is_none());
+}
+
+#[test]
+fn shortest_path_ordering_test() {
+    sample_graph!(s, b, c, d);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let mut paths: Vec<ShortestPath<_, _>> = vec![&b, &d, &s, &c]
+        .into_iter()
+        .map(|end| dijkstra.find_shorted_path(vec![&s], vec![end]).into())
+        .collect();
+    paths.push(ShortestPath {
+        route: vec![&s, &d],
+        weight: SimpleWeight {
+            weight: 15,
+            is_infinity: false,
+        },
+    });
+    paths.sort();
+
+    let sorted: Vec<_> = paths
+        .iter()
+        .map(|path| (path.route.last().unwrap().name.as_str(), path.weight.weight))
+        .collect();
+    assert_eq!(
+        sorted,
+        vec![("S", 0), ("C", 3), ("D", 15), ("D", 15), ("B", 24)]
+    );
+    assert_eq!(paths[2].hops(), 1);
+    assert_eq!(paths[3].hops(), 2);
+}
+
+#[test]
+fn duplicate_vertex_test() {
+    sample_graph!(s, b, c, d);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d, &b]);
+    assert_eq!(dijkstra.vertices(), &[&s, &b, &c, &d][..]);
+    assert_eq!(dijkstra.index_of(&b), Some(1));
+    let (route, weight) = dijkstra.shortest_path(&s, &d).unwrap();
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 15);
+}
+
+#[test]
+fn blocked_edge_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    c.add_edge(&d, 12);
+    c.edges[0].weight = SimpleWeight::infinity();
+    s.add_edge(&b, 24);
+    s.add_edge(&c, 3);
+    s.add_edge(&d, 20);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    let (route, weight) = dijkstra.shortest_path(&s, &d).unwrap();
+
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight.weight, 20);
+    assert_eq!(dijkstra.shortest_distance(&c, &d), None);
+}
+
+#[test]
+fn rebuild_test() {
+    sample_graph!(s, b, c, d);
+    let e = SimpleVertex::new("E".to_owned());
+    let mut t = SimpleVertex::new("T".to_owned());
+    t.add_edge(&e, 7);
+
+    let mut dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    assert_eq!(dijkstra.shortest_distance(&s, &d).unwrap().weight, 15);
+
+    dijkstra.rebuild(vec![&t, &e]);
+    assert_eq!(dijkstra.vertices(), &[&t, &e][..]);
+    assert_eq!(dijkstra.index_of(&s), None);
+    assert_eq!(dijkstra.shortest_distance(&t, &e).unwrap().weight, 7);
+}
+
+#[test]
+fn shortest_path_tree_edges_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let tree = dijkstra.shortest_path_tree_edges(&s);
+    let tree: Vec<_> = tree
+        .iter()
+        .map(|&(v, edge)| (v.name.as_str(), edge.weight.weight))
+        .collect();
+
+    assert_eq!(tree, vec![("B", 24), ("C", 3), ("D", 12)]);
+    let (_, d_edge) = dijkstra
+        .shortest_path_tree_edges(&s)
+        .into_iter()
+        .find(|&(v, _)| v == &d)
+        .unwrap();
+    assert!(std::ptr::eq(d_edge, &c.edges[0]));
+}
+
+#[test]
+fn distances_to_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let distances = dijkstra.distances_to(vec![&d]);
+    let mut distances: Vec<_> = distances
+        .iter()
+        .map(|(v, weight)| (v.name.as_str(), weight.weight))
+        .collect();
+    distances.sort();
+
+    assert_eq!(distances, vec![("C", 12), ("D", 0), ("S", 15)]);
+}
+
+#[test]
+fn is_on_shortest_path_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    assert!(dijkstra.is_on_shortest_path(&s, &d, &c.edges[0]));
+    assert!(dijkstra.is_on_shortest_path(&s, &d, &s.edges[1]));
+    assert!(!dijkstra.is_on_shortest_path(&s, &d, &s.edges[0]));
+    assert!(!dijkstra.is_on_shortest_path(&s, &d, &s.edges[2]));
+    assert!(!dijkstra.is_on_shortest_path(&b, &d, &c.edges[0]));
+}
+
+#[test]
+fn edge_overrides_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let id = dijkstra.edge_id(&c, &c.edges[0]).unwrap();
+    assert_eq!(
+        id,
+        EdgeId {
+            from: 2,
+            to: 3,
+            index: 0
+        }
+    );
+
+    let mut overrides = Map::new();
+    overrides.insert(
+        id,
+        SimpleWeight {
+            weight: 30,
+            is_infinity: false,
+        },
+    );
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_overrides(vec![&s], vec![&d], &overrides)
+        .unwrap();
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight.weight, 20);
+
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 15);
+}
+
+#[test]
+fn settle_order_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let order: Vec<_> = dijkstra
+        .settle_order(vec![&s])
+        .map(|(v, weight)| (v.name.as_str(), weight.weight))
+        .collect();
+    assert_eq!(order, vec![("S", 0), ("C", 3), ("D", 15), ("B", 24)]);
+
+    let budget = SimpleWeight {
+        weight: 15,
+        is_infinity: false,
+    };
+    let lazy: Vec<_> = dijkstra
+        .settle_order(vec![&s])
+        .take_while(|(_, weight)| weight.weight < 16)
+        .map(|(v, _)| v)
+        .collect();
+    let within: Vec<_> = dijkstra
+        .reachable_within(vec![&s], &budget)
+        .into_iter()
+        .map(|(v, _)| v)
+        .collect();
+    assert_eq!(lazy, within);
+    assert_eq!(within, vec![&s, &c, &d]);
+}
+
+#[test]
+fn astar_upper_bound_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    let heuristic = |v: &SimpleVertex| simple(if v.name == "C" { 12 } else { 0 });
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_astar(vec![&s], vec![&d], heuristic, None)
+        .unwrap();
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 15);
+
+    let (route, _) = dijkstra
+        .find_shortest_path_astar(vec![&s], vec![&d], heuristic, Some(simple(15)))
+        .unwrap();
+    assert_eq!(route, vec![&s, &c, &d]);
+
+    assert!(dijkstra
+        .find_shortest_path_astar(vec![&s], vec![&d], heuristic, Some(simple(14)))
+        .is_none());
+}
+
+#[test]
+fn second_shortest_path_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let (shortest, shortest_weight) = dijkstra.shortest_path(&s, &d).unwrap();
+    let (route, weight) = dijkstra.second_shortest_path(&s, &d).unwrap();
+    assert_ne!(route, shortest);
+    assert!(weight >= shortest_weight);
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight.weight, 20);
+
+    assert!(dijkstra.second_shortest_path(&s, &b).is_none());
+}
+
+#[test]
+fn shortest_path_by_key_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let (route, weight) = dijkstra.shortest_path_by_key("S", "D").unwrap();
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 15);
+
+    assert_eq!(dijkstra.vertex_by_key("B"), Some(&b));
+    assert!(dijkstra.shortest_path_by_key("S", "X").is_none());
+
+    let mut dijkstra = dijkstra;
+    dijkstra.rebuild(vec![&d, &c]);
+    assert_eq!(dijkstra.vertex_by_key("C"), Some(&c));
+    assert_eq!(dijkstra.vertex_by_key("S"), None);
+}
+
+#[test]
+fn max_settled_test() {
+    let leaves: Vec<_> = (0..1000)
+        .map(|i| SimpleVertex::new(format!("L{}", i)))
+        .collect();
+    let mut s = SimpleVertex::new("S".to_owned());
+    for (i, leaf) in leaves.iter().enumerate() {
+        s.add_edge(leaf, i + 1);
+    }
+
+    let dijkstra = Dijkstra::new(iter::once(&s).chain(leaves.iter()));
+    let far = &leaves[leaves.len() - 1];
+
+    assert_eq!(
+        dijkstra.find_shortest_path_settling(vec![&s], vec![far], Some(100)),
+        Err(Error::LimitExceeded)
+    );
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_settling(vec![&s], vec![far], Some(1001))
+        .unwrap();
+    assert_eq!(route, vec![&s, far]);
+    assert_eq!(weight.weight, 1000);
+}
+
+#[test]
+fn network_metrics_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    // S reaches B, C and D at 24, 3 and 15, and C reaches D at 12.
+    assert_eq!(dijkstra.average_path_length(), Some(54.0 / 4.0));
+    assert_eq!(dijkstra.connectivity_ratio(), 4.0 / 12.0);
+
+    let lonely = Dijkstra::new(vec![&b]);
+    assert_eq!(lonely.average_path_length(), None);
+    assert_eq!(lonely.connectivity_ratio(), 0.0);
+}
+
+#[test]
+fn tiered_cost_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    c.add_edge(&d, 12);
+    s.add_edge(&c, 12);
+    s.add_edge(&d, 20);
+
+    let dijkstra = Dijkstra::new(vec![&s, &c, &d]);
+    assert_eq!(dijkstra.shortest_path(&s, &d).unwrap().0, vec![&s, &d]);
+
+    // Past a fare of 10, every further edge costs a flat 1.
+    let (route, weight) = dijkstra
+        .find_shortest_path_by_cost(vec![&s], vec![&d], |accumulated, edge| {
+            if accumulated.weight >= 10 {
+                simple(1)
+            } else {
+                edge.get_weight().clone()
+            }
+        })
+        .unwrap();
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 13);
+}
+
+#[test]
+fn path_segments_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let (segments, weight) = dijkstra
+        .find_shortest_path_segments(vec![&s], vec![&d])
+        .unwrap();
+    let segments: Vec<_> = segments
+        .iter()
+        .map(|&(from, edge, to)| (from.name.as_str(), edge.weight.weight, to.name.as_str()))
+        .collect();
+    assert_eq!(segments, vec![("S", 3, "C"), ("C", 12, "D")]);
+    assert_eq!(weight.weight, 15);
+}
+
+#[derive(Default)]
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
+
+#[test]
+fn custom_hasher_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::with_hasher(
+        vec![&s, &b, &c, &d],
+        BuildHasherDefault::<FnvHasher>::default(),
+    );
+
+    assert_eq!(dijkstra.index_of(&c), Some(2));
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.weight, 15);
+}
+
+#[test]
+fn distance_quantile_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    assert_eq!(dijkstra.distance_quantile(&s, 0.5), Some(simple(15)));
+    assert_eq!(dijkstra.distance_quantile(&s, 0.0), Some(simple(3)));
+    assert_eq!(dijkstra.distance_quantile(&s, 1.0), Some(simple(24)));
+    assert_eq!(dijkstra.distance_quantile(&s, 1.5), None);
+    assert_eq!(dijkstra.distance_quantile(&b, 0.5), None);
+}
+
+#[test]
+fn decrease_edge_weight_test() {
+    let c = SimpleVertex::new("C".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut x = SimpleVertex::new("X".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    b.add_edge(&c, 1);
+    a.add_edge(&b, 1);
+    x.add_edge(&b, 10);
+    s.add_edge(&a, 5);
+    s.add_edge(&x, 1);
+
+    let dijkstra = Dijkstra::new(vec![&s, &x, &a, &b, &c]);
+    let mut tree = dijkstra.source_tree(&s);
+    assert_eq!(dijkstra.tree_path(&tree, &c).unwrap().1.weight, 7);
+
+    // No edge from A to C, and the edge from X to B already weighs less than 12.
+    assert!(!dijkstra.decrease_edge_weight(&mut tree, &a, &c, simple(0)));
+    assert!(!dijkstra.decrease_edge_weight(&mut tree, &x, &b, simple(12)));
+    assert_eq!(dijkstra.tree_path(&tree, &c).unwrap().1.weight, 7);
+
+    assert!(dijkstra.decrease_edge_weight(&mut tree, &x, &b, simple(1)));
+    assert!(!dijkstra.decrease_edge_weight(&mut tree, &x, &b, simple(1)));
+
+    let mut overrides = Map::new();
+    overrides.insert(dijkstra.edge_id(&x, &x.edges[0]).unwrap(), simple(1));
+    for &v in dijkstra.vertices() {
+        let recomputed = dijkstra.find_shortest_path_with_overrides(vec![&s], vec![v], &overrides);
+        assert_eq!(dijkstra.tree_path(&tree, v), recomputed);
+    }
+    assert_eq!(
+        dijkstra.tree_path(&tree, &c).unwrap(),
+        (vec![&s, &x, &b, &c], simple(3))
+    );
+}
+
+#[test]
+fn max_edge_weight_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    b.add_edge(&d, 4);
+    a.add_edge(&b, 4);
+    s.add_edge(&a, 4);
+    s.add_edge(&d, 10);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &d]);
+    assert_eq!(dijkstra.shortest_path(&s, &d).unwrap().0, vec![&s, &d]);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_max_edge(vec![&s], vec![&d], &simple(5))
+        .unwrap();
+    assert_eq!(route, vec![&s, &a, &b, &d]);
+    assert_eq!(weight.weight, 12);
+
+    assert!(dijkstra
+        .find_shortest_path_with_max_edge(vec![&s], vec![&d], &simple(3))
+        .is_none());
+}
+
+#[test]
+fn combined_options_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    b.add_edge(&d, 4);
+    a.add_edge(&b, 4);
+    s.add_edge(&a, 4);
+    s.add_edge(&d, 10);
+    s.add_edge(&b, 9);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &d]);
+    let direct = dijkstra.edge_id(&s, &s.edges[1]).unwrap();
+    let avoid_edges: Set<_> = vec![direct].into_iter().collect();
+    let avoid_vertices: Set<_> = vec![&a].into_iter().collect();
+
+    let options = SearchOptions {
+        max_edge_weight: Some(simple(5)),
+        ..SearchOptions::default()
+    };
+    assert_eq!(
+        dijkstra.search(vec![&s], vec![&d], &options).ok(),
+        dijkstra.find_shortest_path_with_max_edge(vec![&s], vec![&d], &simple(5))
+    );
+    let options = SearchOptions {
+        max_hops: Some(2),
+        ..options
+    };
+    assert_eq!(
+        dijkstra.search(vec![&s], vec![&d], &options),
+        Err(Error::NoPath)
+    );
+
+    // The lighter way to B takes both hops, so D is only reachable through S -> B.
+    let options = SearchOptions {
+        avoid_edges: Some(&avoid_edges),
+        max_hops: Some(2),
+        ..SearchOptions::default()
+    };
+    let (route, weight) = dijkstra.search(vec![&s], vec![&d], &options).unwrap();
+    assert_eq!(route, vec![&s, &b, &d]);
+    assert_eq!(weight.weight, 13);
+
+    let cost = |_: &SimpleVertex| simple(1);
+    let options = SearchOptions {
+        avoid_edges: Some(&avoid_edges),
+        vertex_cost: Some(&cost),
+        ..SearchOptions::default()
+    };
+    let (route, weight) = dijkstra.search(vec![&s], vec![&d], &options).unwrap();
+    assert_eq!(route, vec![&s, &b, &d]);
+    assert_eq!(weight.weight, 14);
+
+    let options = SearchOptions {
+        avoid_vertices: Some(&avoid_vertices),
+        ..SearchOptions::default()
+    };
+    let (route, weight) = dijkstra.search(vec![&s], vec![&d], &options).unwrap();
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight.weight, 10);
+    let options = SearchOptions {
+        budget: Some(simple(9)),
+        ..options
+    };
+    assert_eq!(
+        dijkstra.search(vec![&s], vec![&d], &options),
+        Err(Error::NoPath)
+    );
+}
+
+#[test]
+fn required_edges_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    let direct = dijkstra.edge_id(&s, &s.edges[2]).unwrap();
+
+    let (route, weight) = dijkstra
+        .shortest_path_with_required_edges(vec![&s], vec![&d], &[direct])
+        .unwrap();
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight.weight, 20);
+
+    let to_b = dijkstra.edge_id(&s, &s.edges[0]).unwrap();
+    assert!(dijkstra
+        .shortest_path_with_required_edges(vec![&s], vec![&d], &[to_b])
+        .is_none());
+}
+
+#[test]
+fn min_total_distance_vertex_test() {
+    sample_graph!(s, b, c, d);
+    let list = vec![&s, &b, &c, &d];
+    let dijkstra = Dijkstra::new(list.clone());
+
+    for targets in [vec![&c, &d], vec![&b, &d], vec![&s]] {
+        let brute_force = list
+            .iter()
+            .filter_map(|&v| {
+                let distances: Option<Vec<_>> = targets
+                    .iter()
+                    .map(|&target| dijkstra.shortest_distance(v, target))
+                    .collect();
+                distances.map(|distances| (v, distances.iter().map(|w| w.weight).sum::<usize>()))
+            })
+            .min_by_key(|&(_, total)| total);
+
+        let found = dijkstra
+            .min_total_distance_vertex(&targets)
+            .map(|(v, weight)| (v, weight.weight));
+        assert_eq!(found, brute_force);
+    }
+
+    assert_eq!(
+        dijkstra.min_total_distance_vertex(&[&c, &d]),
+        Some((&c, simple(12)))
+    );
+}
+
+#[test]
+fn implicit_search_test() {
+    let neighbors = |&n: &u32| vec![(n + 1, 1u32), (n * 2, 1)];
+    let (route, weight) = implicit(1u32, neighbors, |&n| n == 10).unwrap();
+    assert_eq!(route, vec![1, 2, 4, 5, 10]);
+    assert_eq!(weight, 4);
+
+    // Doubling costs more than walking up, so small steps win.
+    let neighbors = |&n: &u32| vec![(n + 1, 1u32), (n * 2, 5)];
+    let (route, weight) = implicit(1u32, neighbors, |&n| n == 4).unwrap();
+    assert_eq!(route, vec![1, 2, 3, 4]);
+    assert_eq!(weight, 3);
+
+    assert!(implicit(1u32, |_: &u32| Vec::<(u32, u32)>::new(), |&n| n == 2).is_none());
+}
+
+#[test]
+fn tie_break_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    a.add_edge(&d, 1);
+    b.add_edge(&d, 1);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 1);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &d]);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_tie_break(vec![&s], vec![&d], |x, y| x.name.cmp(&y.name))
+        .unwrap();
+    assert_eq!(route, vec![&s, &a, &d]);
+    assert_eq!(weight.weight, 2);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_tie_break(vec![&s], vec![&d], |x, y| y.name.cmp(&x.name))
+        .unwrap();
+    assert_eq!(route, vec![&s, &b, &d]);
+    assert_eq!(weight.weight, 2);
+}
+
+#[test]
+fn edge_disjoint_alternative_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    a.add_edge(&d, 1);
+    b.add_edge(&d, 2);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 2);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &d]);
+    let (best, _) = dijkstra.shortest_path(&s, &d).unwrap();
+    assert_eq!(best, vec![&s, &a, &d]);
+
+    let (route, weight) = dijkstra
+        .shortest_edge_disjoint_alternative(&s, &d, &best)
+        .unwrap();
+    assert_eq!(route, vec![&s, &b, &d]);
+    assert_eq!(weight.weight, 4);
+
+    assert!(dijkstra
+        .shortest_edge_disjoint_alternative(&s, &d, &[&s, &a, &d, &b, &s, &b, &d])
+        .is_none());
+}
+
+#[test]
+fn most_reliable_path_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    a.add_edge(&d, 1);
+    b.add_edge(&d, 2);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 2);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &d]);
+    // The slower arm through B is the more reliable one.
+    let reliability = |edge: &SimpleEdge| if edge.weight.weight == 2 { 9 } else { 5 };
+
+    let (route, weight) = dijkstra
+        .most_reliable_path_within(&s, &d, &simple(2), reliability)
+        .unwrap();
+    assert_eq!(route, vec![&s, &b, &d]);
+    assert_eq!(weight.weight, 4);
+
+    let (route, weight) = dijkstra
+        .most_reliable_path_within(&s, &d, &simple(1), reliability)
+        .unwrap();
+    assert_eq!(route, vec![&s, &a, &d]);
+    assert_eq!(weight.weight, 2);
+}
+
+#[test]
+fn try_new_test() {
+    sample_graph!(s, b, c, d);
+
+    let empty: Vec<&SimpleVertex> = vec![];
+    assert_eq!(Dijkstra::try_new(empty).err(), Some(Error::EmptyGraph));
+
+    let dijkstra = Dijkstra::try_new(vec![&s, &b, &c, &d]).unwrap();
+    assert_eq!(dijkstra.shortest_distance(&s, &d), Some(simple(15)));
+}
+
+#[test]
+fn all_edges_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let edges: Vec<_> = dijkstra
+        .all_edges()
+        .map(|(from, edge)| {
+            (
+                from.name.as_str(),
+                edge.to.name.as_str(),
+                edge.weight.weight,
+            )
+        })
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            ("S", "B", 24),
+            ("S", "C", 3),
+            ("S", "D", 20),
+            ("C", "D", 12)
+        ]
+    );
+}
+
+#[test]
+fn vertex_penalty_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let moderate = |v: &SimpleVertex| {
+        if v.name == "C" {
+            Some(simple(10))
+        } else {
+            None
+        }
+    };
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_penalties(vec![&s], vec![&d], moderate)
+        .unwrap();
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight.weight, 20);
+
+    // The penalized vertex is still used when it is the only way.
+    let (route, weight) = dijkstra
+        .find_shortest_path_with_penalties(vec![&s], vec![&c], moderate)
+        .unwrap();
+    assert_eq!(route, vec![&s, &c]);
+    assert_eq!(weight.weight, 13);
+
+    let infinite = |v: &SimpleVertex| {
+        if v.name == "C" {
+            Some(SimpleWeight::infinity())
+        } else {
+            None
+        }
+    };
+    let allowed: Set<_> = vec![&s, &b, &d].into_iter().collect();
+    for end in [&b, &c, &d] {
+        assert_eq!(
+            dijkstra.find_shortest_path_with_penalties(vec![&s], vec![end], infinite),
+            dijkstra.find_shortest_path_within(&allowed, vec![&s], vec![end])
+        );
+    }
+}
+
+#[test]
+fn critical_vertices_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut x = SimpleVertex::new("X".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    // Both arms from S meet at the bottleneck X before D.
+    x.add_edge(&d, 1);
+    a.add_edge(&x, 1);
+    b.add_edge(&x, 2);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 1);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &x, &d]);
+    assert_eq!(dijkstra.critical_vertices(&s, &d), vec![&x]);
+    assert!(dijkstra.critical_vertices(&s, &a).is_empty());
+    assert!(dijkstra.critical_vertices(&d, &s).is_empty());
+}
+
+#[test]
+fn degenerate_graph_test() {
+    let v = SimpleVertex::new("V".to_owned());
+    let outside = SimpleVertex::new("O".to_owned());
+
+    let single = Dijkstra::new(vec![&v]);
+    assert_eq!(
+        single.find_shorted_path(vec![&v], vec![&v]),
+        (vec![&v], simple(0))
+    );
+    assert_eq!(single.shortest_path(&v, &v), Some((vec![&v], simple(0))));
+    assert_eq!(single.shortest_path(&v, &outside), None);
+    assert_eq!(single.shortest_path(&outside, &v), None);
+    assert_eq!(
+        single.find_shortest_path_limited(vec![&v], vec![&outside], 10),
+        Err(Error::NoPath)
+    );
+    assert_eq!(single.diameter(), Some(simple(0)));
+    assert_eq!(single.second_shortest_path(&v, &v), None);
+    assert_eq!(single.shortest_cycle_through(&v), None);
+    assert_eq!(single.connectivity_ratio(), 0.0);
+    assert_eq!(single.connected_components(), vec![vec![&v]]);
+
+    let empty = Dijkstra::<SimpleVertex, SimpleEdge, SimpleWeight>::new(vec![]);
+    assert_eq!(empty.shortest_path(&v, &v), None);
+    assert_eq!(
+        empty.find_shortest_path_limited(vec![&v], vec![&v], 10),
+        Err(Error::NoPath)
+    );
+    assert!(empty.distances_from(vec![&v]).is_empty());
+    assert_eq!(empty.eccentricity(&v), None);
+    assert_eq!(empty.diameter(), None);
+    assert_eq!(empty.average_path_length(), None);
+    assert!(empty.minimum_spanning_tree().is_empty());
+    assert!(empty.strongly_connected_components().is_empty());
+    assert!(empty.shortest_path_tree_edges(&v).is_empty());
+}
+
+#[test]
+fn pairs_within_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let pairs_of = |budget| -> Set<(&str, &str, usize)> {
+        dijkstra
+            .pairs_within(simple(budget))
+            .into_iter()
+            .map(|(from, to, weight)| (from.name.as_str(), to.name.as_str(), weight.weight))
+            .collect()
+    };
+
+    assert!(pairs_of(2).is_empty());
+    assert_eq!(
+        pairs_of(12),
+        vec![("S", "C", 3), ("C", "D", 12)].into_iter().collect()
+    );
+    let mut previous = Set::new();
+    for budget in 0..30 {
+        let pairs = pairs_of(budget);
+        assert!(pairs.is_superset(&previous));
+        previous = pairs;
+    }
+    assert_eq!(previous.len(), 4);
+}
+
+#[test]
+fn trace_search_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    assert_eq!(
+        dijkstra.trace_search(vec![&s], vec![&d]),
+        vec![
+            HeapOperation::Push(&s),
+            HeapOperation::Pop(&s),
+            HeapOperation::Push(&b),
+            HeapOperation::Push(&c),
+            HeapOperation::Push(&d),
+            HeapOperation::Pop(&c),
+            HeapOperation::Push(&d),
+            HeapOperation::Pop(&d),
+        ]
+    );
+}
+
+#[test]
+fn approximate_shortest_path_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    for end in [&s, &b, &c, &d] {
+        assert_eq!(
+            dijkstra.approximate_shortest_path(&s, end, 100),
+            dijkstra.shortest_path(&s, end)
+        );
+    }
+
+    let d = SimpleVertex::new("D".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+
+    // A beam of one follows the lighter first edge to B and misses the route via C.
+    b.add_edge(&d, 10);
+    c.add_edge(&d, 1);
+    a.add_edge(&b, 1);
+    a.add_edge(&c, 2);
+
+    let dijkstra = Dijkstra::new(vec![&a, &b, &c, &d]);
+    assert_eq!(
+        dijkstra.approximate_shortest_path(&a, &d, 1),
+        Some((vec![&a, &b, &d], simple(11)))
+    );
+    assert_eq!(
+        dijkstra.approximate_shortest_path(&a, &d, 2),
+        Some((vec![&a, &c, &d], simple(3)))
+    );
+}
+
+#[test]
+fn parallel_edge_id_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+    s.add_edge(&d, 5);
+    s.add_edge(&d, 7);
+
+    let dijkstra = Dijkstra::new(vec![&s, &d]);
+    let first = dijkstra.edge_id(&s, &s.edges[0]).unwrap();
+    let second = dijkstra.edge_id(&s, &s.edges[1]).unwrap();
+    assert_ne!(first, second);
+    assert_eq!((second.from, second.to, second.index), (0, 1, 1));
+
+    let mut overrides = Map::new();
+    overrides.insert(first, simple(9));
+    assert_eq!(
+        dijkstra.find_shortest_path_with_overrides(vec![&s], vec![&d], &overrides),
+        Some((vec![&s, &d], simple(7)))
+    );
+    overrides.insert(second, simple(2));
+    assert_eq!(
+        dijkstra.find_shortest_path_with_overrides(vec![&s], vec![&d], &overrides),
+        Some((vec![&s, &d], simple(2)))
+    );
+}
+
+#[test]
+fn custom_edge_id_test() {
+    // Ids of the edges' own, which do not follow the indices of the `Dijkstra`.
+    let id = |n: usize| EdgeId {
+        from: 100 + n,
+        to: 200 + n,
+        index: 0,
+    };
+    let d = SimpleVertex::new("D".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+    c.add_edge_with_id(&d, 12, id(3));
+    s.add_edge_with_id(&b, 24, id(0));
+    s.add_edge_with_id(&c, 3, id(1));
+    s.add_edge_with_id(&d, 20, id(2));
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    assert_eq!(dijkstra.edge_id(&c, &c.edges[0]), Some(id(3)));
+
+    let (route, weight) = dijkstra
+        .shortest_path_with_required_edges(vec![&s], vec![&d], &[id(2)])
+        .unwrap();
+    assert_eq!(route, vec![&s, &d]);
+    assert_eq!(weight, simple(20));
+
+    let (route, weight) = dijkstra
+        .shortest_path_with_required_edges(vec![&s], vec![&d], &[id(3)])
+        .unwrap();
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight, simple(15));
+
+    assert!(dijkstra
+        .shortest_path_with_required_edges(vec![&s], vec![&d], &[id(7)])
+        .is_none());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct SumAndMax {
+    sum: usize,
+    max: usize,
+}
+
+impl Accumulator<SimpleWeight> for SumAndMax {
+    type Key = (usize, usize);
+
+    fn combine(&self, edge_weight: &SimpleWeight) -> Self {
+        SumAndMax {
+            sum: self.sum + edge_weight.weight,
+            max: self.max.max(edge_weight.weight),
+        }
+    }
+
+    fn key(&self) -> (usize, usize) {
+        (self.sum, self.max)
+    }
+}
+
+#[test]
+fn accumulated_cost_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+
+    // Both routes sum to 4, but the one via B has no edge heavier than 2.
+    b.add_edge(&d, 2);
+    c.add_edge(&d, 3);
+    a.add_edge(&c, 1);
+    a.add_edge(&b, 2);
+
+    let dijkstra = Dijkstra::new(vec![&a, &b, &c, &d]);
+    let initial = SumAndMax { sum: 0, max: 0 };
+    assert_eq!(
+        dijkstra.find_shortest_path_accumulated(vec![&a], vec![&d], initial.clone()),
+        Some((vec![&a, &b, &d], SumAndMax { sum: 4, max: 2 }))
+    );
+    assert_eq!(
+        dijkstra.find_shortest_path_accumulated(vec![&a], vec![&c], initial.clone()),
+        Some((vec![&a, &c], SumAndMax { sum: 1, max: 1 }))
+    );
+    assert_eq!(
+        dijkstra.find_shortest_path_accumulated(vec![&d], vec![&a], initial),
+        None
+    );
+}
+
+#[test]
+fn best_sightseeing_path_test() {
+    let e = SimpleVertex::new("E".to_owned());
+    let mut p2 = SimpleVertex::new("P2".to_owned());
+    let mut p1 = SimpleVertex::new("P1".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    p2.add_edge(&e, 4);
+    p1.add_edge(&p2, 4);
+    p1.add_edge(&e, 5);
+    s.add_edge(&p1, 4);
+    s.add_edge(&e, 8);
+
+    let dijkstra = Dijkstra::new(vec![&s, &p1, &p2, &e]);
+    let pois: Set<_> = vec![&p1, &p2].into_iter().collect();
+
+    assert_eq!(
+        dijkstra.best_sightseeing_path(&s, &e, &simple(12), &pois),
+        Some((vec![&s, &p1, &p2, &e], simple(12)))
+    );
+    assert_eq!(
+        dijkstra.best_sightseeing_path(&s, &e, &simple(11), &pois),
+        Some((vec![&s, &p1, &e], simple(9)))
+    );
+    assert_eq!(
+        dijkstra.best_sightseeing_path(&s, &e, &simple(8), &pois),
+        Some((vec![&s, &e], simple(8)))
+    );
+    assert_eq!(
+        dijkstra.shortest_path(&s, &e),
+        Some((vec![&s, &e], simple(8)))
+    );
+    assert_eq!(
+        dijkstra.best_sightseeing_path(&s, &e, &simple(7), &pois),
+        None
+    );
+}
+
+#[test]
+fn dijkstra_clone_test() {
+    sample_graph!(s, b, c, d);
+    let mut original = Dijkstra::new(vec![&s, &b, &c, &d]);
+    original.set_hop_tiebreak(false);
+
+    let mut copy = original.clone();
+    for end in [&b, &c, &d] {
+        assert_eq!(copy.shortest_path(&s, end), original.shortest_path(&s, end));
+    }
+
+    copy.rebuild(vec![&c, &d]);
+    copy.set_hop_tiebreak(true);
+    assert_eq!(copy.shortest_path(&s, &d), None);
+    assert_eq!(copy.shortest_path(&c, &d), Some((vec![&c, &d], simple(12))));
+    assert_eq!(original.vertices(), &[&s, &b, &c, &d]);
+    assert_eq!(
+        original.shortest_path(&s, &d),
+        Some((vec![&s, &c, &d], simple(15)))
+    );
+}
+
+#[test]
+fn tree_to_dot_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let dot = dijkstra.tree_to_dot(&s, |v| v.name.clone());
+    assert_eq!(
+        dot,
+        "digraph {\n    \
+         0 [label=\"S (0)\"];\n    \
+         1 [label=\"B (24)\"];\n    \
+         2 [label=\"C (3)\"];\n    \
+         3 [label=\"D (15)\"];\n    \
+         0 -> 1 [label=\"24\", style=bold];\n    \
+         0 -> 2 [label=\"3\", style=bold];\n    \
+         0 -> 3 [label=\"20\", color=gray];\n    \
+         2 -> 3 [label=\"12\", style=bold];\n\
+         }\n"
+    );
+
+    let dot = dijkstra.tree_to_dot(&c, |v| v.name.clone());
+    assert!(dot.contains("0 [label=\"S\"];"));
+    assert_eq!(dot.matches("style=bold").count(), 1);
+}
+
+#[test]
+fn load_aware_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    // The edge from C to D is nearly full, which costs more than the detour it saves.
+    let crowded: *const SimpleEdge = &c.edges[0];
+    let load = |edge: &SimpleEdge| {
+        if std::ptr::eq(edge, crowded) {
+            (9, 10)
+        } else {
+            (0, 10)
+        }
+    };
+    let penalty = |utilization: f64| simple((utilization * utilization * 10.0) as usize);
+    assert_eq!(
+        dijkstra.find_shortest_path_with_load(vec![&s], vec![&d], load, penalty),
+        Some((vec![&s, &d], simple(20)))
+    );
+
+    let free = |_: &SimpleEdge| (0, 10);
+    assert_eq!(
+        dijkstra.find_shortest_path_with_load(vec![&s], vec![&d], free, penalty),
+        Some((vec![&s, &c, &d], simple(15)))
+    );
+
+    let full = |edge: &SimpleEdge| {
+        if std::ptr::eq(edge, crowded) {
+            (10, 10)
+        } else {
+            (0, 10)
+        }
+    };
+    let flat = |_| simple(0);
+    assert_eq!(
+        dijkstra.find_shortest_path_with_load(vec![&s], vec![&d], full, flat),
+        Some((vec![&s, &d], simple(20)))
+    );
+}
+
+#[test]
+fn tree_diff_test() {
+    let x = SimpleVertex::new("X".to_owned());
+    let c = SimpleVertex::new("C".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    b.add_edge(&c, 1);
+    a.add_edge(&b, 1);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 5);
+    s.add_edge(&x, 2);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &c, &x]);
+    let before = dijkstra.source_tree(&s);
+    let mut after = dijkstra.source_tree(&s);
+    assert!(dijkstra.tree_diff(&before, &after).is_empty());
+
+    // Only B, which now arrives straight from S, and C below it are affected.
+    assert!(dijkstra.decrease_edge_weight(&mut after, &s, &b, simple(1)));
+    assert_eq!(
+        dijkstra.tree_diff(&before, &after),
+        vec![
+            (
+                &b,
+                Change {
+                    distance: (Some(simple(2)), Some(simple(1))),
+                    predecessor: (Some(&a), Some(&s)),
+                }
+            ),
+            (
+                &c,
+                Change {
+                    distance: (Some(simple(3)), Some(simple(2))),
+                    predecessor: (Some(&b), Some(&b)),
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn shortest_path_bottleneck_test() {
+    sample_graph!(s, b, c, d);
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    assert_eq!(
+        dijkstra.shortest_path_bottleneck(&s, &d),
+        Some((vec![&s, &c, &d], simple(15), simple(3)))
+    );
+    assert_eq!(
+        dijkstra.shortest_path_bottleneck(&c, &d),
+        Some((vec![&c, &d], simple(12), simple(12)))
+    );
+    assert_eq!(
+        dijkstra.shortest_path_bottleneck(&s, &s),
+        Some((vec![&s], simple(0), SimpleWeight::infinity()))
+    );
+    assert_eq!(dijkstra.shortest_path_bottleneck(&d, &s), None);
+}
+
+#[test]
+fn end_tie_break_test() {
+    let a = SimpleVertex::new("A".to_owned());
+    let b = SimpleVertex::new("B".to_owned());
+    let far = SimpleVertex::new("F".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    s.add_edge(&a, 5);
+    s.add_edge(&b, 5);
+    s.add_edge(&far, 6);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &far]);
+    let prefer = |name: &'static str| {
+        move |x: &SimpleVertex, y: &SimpleVertex| (y.name == name).cmp(&(x.name == name))
+    };
+
+    for ends in [vec![&a, &b, &far], vec![&far, &b, &a]] {
+        assert_eq!(
+            dijkstra.find_shortest_path_with_end_tie_break(vec![&s], ends.clone(), prefer("B")),
+            Some((vec![&s, &b], simple(5)))
+        );
+        assert_eq!(
+            dijkstra.find_shortest_path_with_end_tie_break(vec![&s], ends.clone(), prefer("A")),
+            Some((vec![&s, &a], simple(5)))
+        );
+        assert_eq!(
+            dijkstra.find_shortest_path_with_end_tie_break(vec![&s], ends, prefer("F")),
+            Some((vec![&s, &a], simple(5)))
+        );
+    }
+}
+
+#[test]
+fn end_tie_break_sees_only_ends_test() {
+    let a = SimpleVertex::new("A".to_owned());
+    let x = SimpleVertex::new("X".to_owned());
+    let b = SimpleVertex::new("B".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    s.add_edge(&a, 2);
+    s.add_edge(&x, 2);
+    s.add_edge(&b, 2);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &x, &b]);
+    let seen = RefCell::new(vec![]);
+    let route = dijkstra.find_shortest_path_with_end_tie_break(
+        vec![&s],
+        vec![&a, &b],
+        |p: &SimpleVertex, q: &SimpleVertex| {
+            seen.borrow_mut().push((p.name.clone(), q.name.clone()));
+            (q.name == "B").cmp(&(p.name == "B"))
+        },
+    );
+    assert_eq!(route, Some((vec![&s, &b], simple(2))));
+    let seen = seen.into_inner();
+    assert!(!seen.is_empty());
+    assert!(seen
+        .iter()
+        .all(|(p, q)| ["A", "B"].contains(&p.as_str()) && ["A", "B"].contains(&q.as_str())));
+}
+
+#[test]
+fn alternatives_iter_test() {
+    let d = SimpleVertex::new("D".to_owned());
+    let mut a = SimpleVertex::new("A".to_owned());
+    let mut b = SimpleVertex::new("B".to_owned());
+    let mut c = SimpleVertex::new("C".to_owned());
+    let mut s = SimpleVertex::new("S".to_owned());
+
+    a.add_edge(&d, 1);
+    b.add_edge(&d, 2);
+    c.add_edge(&d, 3);
+    s.add_edge(&a, 1);
+    s.add_edge(&b, 2);
+    s.add_edge(&c, 3);
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &c, &d]);
+    let mut alternatives = dijkstra.alternatives_iter(&s, &d, 0.5);
+
+    assert_eq!(alternatives.next(), Some((vec![&s, &a, &d], simple(2))));
+    assert_eq!(alternatives.next(), Some((vec![&s, &b, &d], simple(4))));
+    assert_eq!(alternatives.next(), Some((vec![&s, &c, &d], simple(6))));
+    assert_eq!(alternatives.next(), None);
+}
+
+#[test]
+fn super_source_tree_test() {
+    sample_graph!(s, b, c, d);
+    let x = SimpleVertex::new("X".to_owned());
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d, &x]);
+
+    for starts in [vec![&s], vec![&c, &b], vec![&s, &c], vec![&x, &d]] {
+        let tree = dijkstra.super_source_tree(starts.clone());
+        let distances = dijkstra.distances_from(starts);
+        for v in [&s, &b, &c, &d, &x] {
+            assert_eq!(
+                dijkstra.tree_path(&tree, v).map(|(_, weight)| weight),
+                distances.get(v).cloned()
+            );
+        }
+    }
+
+    let tree = dijkstra.super_source_tree(vec![&s, &c]);
+    assert_eq!(
+        dijkstra.tree_path(&tree, &d),
+        Some((vec![&c, &d], simple(12)))
+    );
+}
@@ -0,0 +1,552 @@
+extern crate subway;
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use subway::csv::matrix_to_csv;
+use subway::dijkstra::*;
+use subway::geojson::path_to_geojson;
+use subway::graph::*;
+use subway::reachability::bfs_shortest_hops;
+use subway::weight::FloatWeight;
+
+fn float(value: f64) -> FloatWeight {
+    FloatWeight::new(value).unwrap()
+}
+
+fn ids<'a>(route: &[&GraphVertex<'a, &'static str, FloatWeight>]) -> Vec<&'static str> {
+    route.iter().map(|v| *v.id()).collect()
+}
+
+#[test]
+fn graph_merge_test() {
+    let mut subway = Graph::from_edges(vec![("S1", "S2", float(1.0)), ("S2", "Hub", float(2.0))]);
+    let bus = Graph::from_edges(vec![("Hub", "B1", float(1.0)), ("B2", "B3", float(1.0))]);
+
+    subway.merge(bus, vec![("B1", "B2")], float(4.0));
+    assert_eq!(subway.len(), 6);
+
+    let dijkstra = subway.dijkstra();
+    let s1 = subway.vertex(&"S1").unwrap();
+    let b3 = subway.vertex(&"B3").unwrap();
+
+    let (route, weight) = dijkstra.find_shorted_path(vec![s1], vec![b3]);
+    assert_eq!(ids(&route), vec!["S1", "S2", "Hub", "B1", "B2", "B3"]);
+    assert_eq!(weight.value(), 9.0);
+}
+
+#[test]
+fn negative_edge_check_test() {
+    let graph = Graph::from_edges(vec![
+        ("S", "A", float(1.0)),
+        ("A", "D", float(1.0)),
+        ("X", "A", float(-5.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+    let s = graph.vertex(&"S").unwrap();
+    let d = graph.vertex(&"D").unwrap();
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_checked(vec![s], vec![d])
+        .unwrap();
+    assert_eq!(ids(&route), vec!["S", "A", "D"]);
+    assert_eq!(weight.value(), 2.0);
+
+    let graph = Graph::from_edges(vec![
+        ("S", "A", float(1.0)),
+        ("A", "D", float(1.0)),
+        ("S", "B", float(2.0)),
+        ("B", "A", float(-3.0)),
+        ("D", "E", float(1.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+    let s = graph.vertex(&"S").unwrap();
+    let e = graph.vertex(&"E").unwrap();
+
+    assert_eq!(
+        dijkstra.find_shortest_path_checked(vec![s], vec![e]),
+        Err(Error::NegativeEdgeEncountered { from: 3, to: 1 })
+    );
+}
+
+#[test]
+fn graph_id_routing_test() {
+    let graph = Graph::from_edges(vec![
+        ("Seoul", "Cityhall", float(2.0)),
+        ("Cityhall", "Jonggak", float(1.5)),
+        ("Seoul", "Hoehyeon", float(1.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+
+    let seoul = graph.vertex(&"Seoul").unwrap();
+    let jonggak = graph.vertex(&"Jonggak").unwrap();
+    let hoehyeon = graph.vertex(&"Hoehyeon").unwrap();
+    assert!(graph.vertex(&"Gangnam").is_none());
+
+    assert_eq!(graph.index_of(&"Jonggak"), dijkstra.index_of(jonggak));
+    assert_eq!(
+        dijkstra.vertex(graph.index_of(&"Hoehyeon").unwrap()),
+        Some(hoehyeon)
+    );
+
+    let (route, weight) = dijkstra.shortest_path(seoul, jonggak).unwrap();
+    assert_eq!(ids(&route), vec!["Seoul", "Cityhall", "Jonggak"]);
+    assert_eq!(weight.value(), 3.5);
+
+    assert!(dijkstra.shortest_path(jonggak, seoul).is_none());
+}
+
+#[test]
+fn path_to_geojson_test() {
+    let graph = Graph::from_edges(vec![
+        ("Seoul", "Cityhall", float(2.0)),
+        ("Cityhall", "Jonggak", float(1.5)),
+    ]);
+    let dijkstra = graph.dijkstra();
+    let seoul = graph.vertex(&"Seoul").unwrap();
+    let jonggak = graph.vertex(&"Jonggak").unwrap();
+
+    let (route, _) = dijkstra.shortest_path(seoul, jonggak).unwrap();
+    let coordinates = |v: &GraphVertex<&str, FloatWeight>| match *v.id() {
+        "Seoul" => (126.9726, 37.5546),
+        "Cityhall" => (126.9769, 37.5658),
+        _ => (126.9831, 37.5703),
+    };
+
+    assert_eq!(
+        path_to_geojson(&route, coordinates),
+        r#"{"type":"LineString","coordinates":[[126.9726,37.5546],[126.9769,37.5658],[126.9831,37.5703]]}"#
+    );
+}
+
+#[test]
+fn graph_incoming_edges_test() {
+    let graph = Graph::from_edges(vec![
+        ("A", "B", float(1.0)),
+        ("B", "C", float(2.0)),
+        ("C", "A", float(4.0)),
+        ("A", "C", float(8.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+    let c = graph.vertex(&"C").unwrap();
+
+    let mut incoming: Vec<_> = c
+        .incoming_edges(&[])
+        .iter()
+        .map(|&(from, edge)| (*from.id(), edge.get_weight().value()))
+        .collect();
+    incoming.sort_by(|a, b| a.0.cmp(b.0));
+    assert_eq!(incoming, vec![("A", 8.0), ("B", 2.0)]);
+
+    let mut distances: Vec<_> = dijkstra
+        .distances_to(vec![c])
+        .iter()
+        .map(|(v, weight)| (*v.id(), weight.value()))
+        .collect();
+    distances.sort_by(|a, b| a.0.cmp(b.0));
+    assert_eq!(distances, vec![("A", 3.0), ("B", 2.0), ("C", 0.0)]);
+}
+
+#[test]
+fn graph_components_test() {
+    let graph = Graph::from_edges(vec![
+        ("A", "B", float(1.0)),
+        ("C", "B", float(1.0)),
+        ("X", "Y", float(1.0)),
+        ("Y", "X", float(1.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+
+    let components: Vec<_> = dijkstra
+        .connected_components()
+        .iter()
+        .map(|component| ids(component))
+        .collect();
+    assert_eq!(components, vec![vec!["A", "B", "C"], vec!["X", "Y"]]);
+
+    let mut components: Vec<_> = dijkstra
+        .strongly_connected_components()
+        .iter()
+        .map(|component| {
+            let mut ids = ids(component);
+            ids.sort();
+            ids
+        })
+        .collect();
+    components.sort();
+    assert_eq!(
+        components,
+        vec![vec!["A"], vec!["B"], vec!["C"], vec!["X", "Y"]]
+    );
+}
+
+#[test]
+fn adjacency_matrix_test() {
+    let none = FloatWeight::infinity();
+    let matrix = vec![
+        vec![none, float(24.0), float(3.0), float(20.0)],
+        vec![none, none, none, none],
+        vec![none, none, none, float(12.0)],
+        vec![none, none, none, none],
+    ];
+    let graph = Graph::from_adjacency_matrix(vec!["S", "B", "C", "D"], matrix).unwrap();
+    let dijkstra = graph.dijkstra();
+
+    let s = graph.vertex(&"S").unwrap();
+    let d = graph.vertex(&"D").unwrap();
+    let (route, weight) = dijkstra.find_shorted_path(vec![s], vec![d]);
+    assert_eq!(ids(&route), vec!["S", "C", "D"]);
+    assert_eq!(weight.value(), 15.0);
+
+    let zero_diagonal = vec![vec![float(0.0), float(2.0)], vec![none, float(0.0)]];
+    let graph = Graph::from_adjacency_matrix(vec!["A", "B"], zero_diagonal).unwrap();
+    assert_eq!(graph.to_edge_list().1, vec![("A", "B", float(2.0))]);
+
+    let ragged = vec![vec![float(1.0), float(1.0)], vec![float(1.0)]];
+    assert_eq!(
+        Graph::from_adjacency_matrix(vec!["A", "B"], ragged).err(),
+        Some(Error::MalformedMatrix)
+    );
+}
+
+#[test]
+fn graph_transpose_test() {
+    let graph = Graph::from_edges(vec![
+        ("S", "B", float(24.0)),
+        ("S", "C", float(3.0)),
+        ("S", "D", float(20.0)),
+        ("C", "D", float(12.0)),
+    ]);
+    let transposed = graph.transpose();
+
+    let (route, weight) = graph
+        .dijkstra()
+        .shortest_path(graph.vertex(&"S").unwrap(), graph.vertex(&"D").unwrap())
+        .unwrap();
+    let (mut reversed, reversed_weight) = transposed
+        .dijkstra()
+        .shortest_path(
+            transposed.vertex(&"D").unwrap(),
+            transposed.vertex(&"S").unwrap(),
+        )
+        .unwrap();
+    reversed.reverse();
+
+    assert_eq!(ids(&reversed), ids(&route));
+    assert_eq!(reversed_weight, weight);
+    assert!(transposed
+        .dijkstra()
+        .shortest_path(
+            transposed.vertex(&"S").unwrap(),
+            transposed.vertex(&"D").unwrap()
+        )
+        .is_none());
+}
+
+#[test]
+fn shortest_cycle_through_test() {
+    let graph = Graph::from_edges(vec![
+        ("A", "B", float(1.0)),
+        ("B", "C", float(1.0)),
+        ("C", "A", float(1.0)),
+        ("A", "D", float(2.0)),
+        ("D", "A", float(2.0)),
+        ("B", "E", float(1.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+
+    let (route, weight) = dijkstra
+        .shortest_cycle_through(graph.vertex(&"A").unwrap())
+        .unwrap();
+    assert_eq!(ids(&route), vec!["A", "B", "C", "A"]);
+    assert_eq!(weight.value(), 3.0);
+
+    assert!(dijkstra
+        .shortest_cycle_through(graph.vertex(&"E").unwrap())
+        .is_none());
+}
+
+#[test]
+fn two_edge_cycle_test() {
+    let graph = Graph::from_edges(vec![
+        ("V", "U", float(1.0)),
+        ("U", "V", float(1.0)),
+        ("U", "W", float(5.0)),
+        ("W", "V", float(5.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+
+    let (route, weight) = dijkstra
+        .shortest_cycle_through(graph.vertex(&"V").unwrap())
+        .unwrap();
+    assert_eq!(ids(&route), vec!["V", "U", "V"]);
+    assert_eq!(weight.value(), 2.0);
+}
+
+#[test]
+fn edge_list_round_trip_test() {
+    let edges = vec![
+        ("S", "B", float(24.0)),
+        ("S", "C", float(3.0)),
+        ("S", "D", float(20.0)),
+        ("C", "D", float(12.0)),
+        ("D", "S", float(1.0)),
+    ];
+    let mut graph = Graph::from_edges(edges.clone());
+    graph.add_vertex("X");
+    assert_eq!(graph.to_edge_list(), (vec!["S", "B", "C", "D", "X"], edges));
+
+    let (vertices, edges) = graph.to_edge_list();
+    let copy = Graph::from_vertices_and_edges(vertices, edges);
+    assert_eq!(copy.len(), 5);
+    assert_eq!(copy.index_of(&"X"), Some(4));
+    let (original, copied) = (graph.dijkstra(), copy.dijkstra());
+    for &from in &["S", "B", "C", "D", "X"] {
+        for &to in &["S", "B", "C", "D", "X"] {
+            let route =
+                original.shortest_path(graph.vertex(&from).unwrap(), graph.vertex(&to).unwrap());
+            let copied_route =
+                copied.shortest_path(copy.vertex(&from).unwrap(), copy.vertex(&to).unwrap());
+            assert_eq!(
+                route.map(|(route, weight)| (ids(&route), weight)),
+                copied_route.map(|(route, weight)| (ids(&route), weight))
+            );
+        }
+    }
+}
+
+#[test]
+fn round_trip_test() {
+    // A reaches B directly, but B only gets back to A around the one-way loop via Y and X.
+    let graph = Graph::from_edges(vec![
+        ("A", "B", float(4.0)),
+        ("B", "Y", float(1.0)),
+        ("Y", "X", float(1.0)),
+        ("X", "A", float(2.0)),
+        ("A", "Z", float(1.0)),
+    ]);
+    let dijkstra = graph.dijkstra();
+    let a = graph.vertex(&"A").unwrap();
+    let b = graph.vertex(&"B").unwrap();
+    let z = graph.vertex(&"Z").unwrap();
+
+    let (there, back) = dijkstra.round_trip(a, b);
+    let (there, there_weight) = there.unwrap();
+    let (back, back_weight) = back.unwrap();
+    assert_eq!(ids(&there), vec!["A", "B"]);
+    assert_eq!(there_weight.value(), 4.0);
+    assert_eq!(ids(&back), vec!["B", "Y", "X", "A"]);
+    assert_eq!(back_weight.value(), 4.0);
+
+    let (there, back) = dijkstra.round_trip(a, z);
+    assert_eq!(there.map(|(route, _)| ids(&route)), Some(vec!["A", "Z"]));
+    assert!(back.is_none());
+}
+
+#[test]
+fn map_weights_test() {
+    let graph = Graph::from_edges(vec![
+        ("S", "B", float(24.0)),
+        ("S", "C", float(3.0)),
+        ("S", "D", float(20.0)),
+        ("C", "D", float(12.0)),
+    ]);
+    let doubled = graph.map_weights(|weight| float(weight.value() * 2.0));
+    assert_eq!(doubled.len(), graph.len());
+
+    let (original, mapped) = (graph.dijkstra(), doubled.dijkstra());
+    for &end in &["B", "C", "D"] {
+        let (route, weight) = original
+            .shortest_path(graph.vertex(&"S").unwrap(), graph.vertex(&end).unwrap())
+            .unwrap();
+        let (mapped_route, mapped_weight) = mapped
+            .shortest_path(doubled.vertex(&"S").unwrap(), doubled.vertex(&end).unwrap())
+            .unwrap();
+        assert_eq!(ids(&mapped_route), ids(&route));
+        assert_eq!(mapped_weight.value(), weight.value() * 2.0);
+    }
+}
+
+#[test]
+fn matrix_to_csv_test() {
+    let labels = ["S", "B", "C", "D"];
+    let graph = Graph::from_edges(vec![
+        ("S", "B", 24u32),
+        ("S", "C", 3),
+        ("S", "D", 20),
+        ("C", "D", 12),
+    ]);
+    let dijkstra = graph.dijkstra();
+
+    let vertices: Vec<_> = labels.iter().map(|id| graph.vertex(id).unwrap()).collect();
+    let pairs: Vec<_> = vertices
+        .iter()
+        .flat_map(|&from| vertices.iter().map(move |&to| (from, to)))
+        .collect();
+    let matrix: Vec<Vec<_>> = dijkstra
+        .distance_matrix(&pairs)
+        .chunks(labels.len())
+        .map(|row| row.to_vec())
+        .collect();
+
+    let mut csv = String::new();
+    matrix_to_csv(&matrix, &labels, &mut csv).unwrap();
+    assert_eq!(csv, ",S,B,C,D\nS,0,24,3,15\nB,,0,,\nC,,,0,12\nD,,,,0\n");
+
+    let mut csv = String::new();
+    matrix_to_csv(&[vec![Some(1)]], &["Seoul, \"Station\""], &mut csv).unwrap();
+    assert_eq!(
+        csv,
+        ",\"Seoul, \"\"Station\"\"\"\n\"Seoul, \"\"Station\"\"\",1\n"
+    );
+}
+
+#[test]
+fn edge_stream_test() {
+    let source = "S,B,24\nS,C,3\nS,D,20\nC,D,12\n";
+    let edges = source.lines().map(|line| {
+        let mut fields = line.split(',');
+        let from = fields.next().unwrap();
+        let to = fields.next().unwrap();
+        let weight = float(fields.next().unwrap().parse().unwrap());
+        (from, to, weight)
+    });
+
+    let graph = Graph::from_edges(edges);
+    assert_eq!(graph.len(), 4);
+    let dijkstra = graph.dijkstra();
+    let (route, weight) = dijkstra
+        .shortest_path(graph.vertex(&"S").unwrap(), graph.vertex(&"D").unwrap())
+        .unwrap();
+    assert_eq!(ids(&route), vec!["S", "C", "D"]);
+    assert_eq!(weight.value(), 15.0);
+}
+
+#[test]
+fn unweighted_edges_test() {
+    let edges = vec![
+        ("A", "B"),
+        ("B", "C"),
+        ("C", "D"),
+        ("A", "E"),
+        ("E", "D"),
+        ("D", "F"),
+        ("B", "F"),
+    ];
+    let graph: Graph<_, u32> = Graph::from_edges_unweighted(edges.clone());
+    let dijkstra = graph.dijkstra();
+    let neighbors = |v: &&str| -> Vec<&str> {
+        edges
+            .iter()
+            .filter(|&&(from, _)| from == *v)
+            .map(|&(_, to)| to)
+            .collect()
+    };
+
+    for &from in &["A", "B", "C", "D", "E", "F"] {
+        for &to in &["A", "B", "C", "D", "E", "F"] {
+            let route = dijkstra
+                .shortest_path(graph.vertex(&from).unwrap(), graph.vertex(&to).unwrap())
+                .map(|(route, hops)| {
+                    let ids: Vec<_> = route.iter().map(|v| *v.id()).collect();
+                    (ids, hops as usize)
+                });
+            let reference = bfs_shortest_hops(from, to, neighbors);
+            assert_eq!(
+                route.as_ref().map(|(_, hops)| *hops),
+                reference.as_ref().map(|(_, hops)| *hops)
+            );
+            if let Some((route, hops)) = route {
+                assert_eq!(route.len(), hops + 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn incremental_build_test() {
+    let mut graph = Graph::new();
+    for &id in &["S", "B", "C", "D", "X"] {
+        graph.add_vertex(id);
+    }
+    assert_eq!(graph.add_vertex("C"), 2);
+    graph.add_edge("C", "D", float(12.0));
+    graph.add_edge("S", "B", float(24.0));
+    graph.add_edge("S", "C", float(3.0));
+    graph.add_edge("S", "D", float(20.0));
+
+    let dijkstra = graph.dijkstra();
+    assert_eq!(dijkstra.vertices().len(), 5);
+    let s = graph.vertex(&"S").unwrap();
+    let (route, weight) = dijkstra
+        .shortest_path(s, graph.vertex(&"D").unwrap())
+        .unwrap();
+    assert_eq!(ids(&route), vec!["S", "C", "D"]);
+    assert_eq!(weight.value(), 15.0);
+    assert!(dijkstra
+        .shortest_path(s, graph.vertex(&"X").unwrap())
+        .is_none());
+}
+
+#[test]
+fn graph_builder_test() {
+    let graph = Graph::builder()
+        .add_vertex("S")
+        .add_vertex("X")
+        .add_edge("C", "D", float(12.0))
+        .add_edge("S", "B", float(24.0))
+        .add_edge("S", "C", float(3.0))
+        .add_edge("S", "D", float(20.0))
+        .build();
+    assert_eq!(graph.index_of(&"X"), Some(1));
+
+    let dijkstra = graph.dijkstra();
+    assert_eq!(dijkstra.vertices().len(), 5);
+    let s = graph.vertex(&"S").unwrap();
+    let (route, weight) = dijkstra
+        .shortest_path(s, graph.vertex(&"D").unwrap())
+        .unwrap();
+    assert_eq!(ids(&route), vec!["S", "C", "D"]);
+    assert_eq!(weight.value(), 15.0);
+    assert!(dijkstra
+        .shortest_path(s, graph.vertex(&"X").unwrap())
+        .is_none());
+}
+
+// Id that is not even equal to itself, so no vertex can ever be found in the vertex index,
+// whether it is hashed or ordered.
+#[derive(Clone)]
+struct UnequalId;
+
+impl Hash for UnequalId {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl PartialEq for UnequalId {
+    fn eq(&self, _: &UnequalId) -> bool {
+        false
+    }
+}
+
+impl Eq for UnequalId {}
+
+impl PartialOrd for UnequalId {
+    fn partial_cmp(&self, other: &UnequalId) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UnequalId {
+    fn cmp(&self, _: &UnequalId) -> Ordering {
+        Ordering::Less
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "out of sync")]
+fn desynced_index_test() {
+    let mut graph: Graph<UnequalId, FloatWeight> = Graph::new();
+    graph.add_vertex(UnequalId);
+    graph.dijkstra();
+}
@@ -0,0 +1,126 @@
+extern crate subway;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::iter;
+use std::sync::atomic::{self, AtomicUsize};
+use subway::dijkstra::*;
+use subway::graph::*;
+
+const SIDE: usize = 100;
+
+static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+// A weight as large as a multi-objective one, counting how often it is cloned.
+struct HeavyWeight {
+    total: u64,
+    _criteria: [u64; 32],
+}
+
+impl HeavyWeight {
+    fn new(total: u64) -> Self {
+        HeavyWeight {
+            total,
+            _criteria: [total; 32],
+        }
+    }
+}
+
+impl Clone for HeavyWeight {
+    fn clone(&self) -> Self {
+        CLONES.fetch_add(1, atomic::Ordering::Relaxed);
+        HeavyWeight::new(self.total)
+    }
+}
+
+impl PartialEq for HeavyWeight {
+    fn eq(&self, other: &Self) -> bool {
+        self.total == other.total
+    }
+}
+
+impl Eq for HeavyWeight {}
+
+impl PartialOrd for HeavyWeight {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeavyWeight {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.total.cmp(&other.total)
+    }
+}
+
+impl Weight for HeavyWeight {
+    fn add(&self, other: &Self) -> Self {
+        HeavyWeight::new(self.total.saturating_add(other.total))
+    }
+
+    fn zero() -> Self {
+        HeavyWeight::new(0)
+    }
+
+    fn one() -> Self {
+        HeavyWeight::new(1)
+    }
+
+    fn infinity() -> Self {
+        HeavyWeight::new(u64::MAX)
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.total == u64::MAX
+    }
+}
+
+#[test]
+fn heavy_weight_grid_test() {
+    let mut graph = Graph::new();
+    for row in 0..SIDE {
+        for column in 0..SIDE {
+            let here = row * SIDE + column;
+            let weight = 1 + (row * 7 + column * 13) as u64 % 10;
+            if column + 1 < SIDE {
+                graph.add_edge(here, here + 1, HeavyWeight::new(weight));
+                graph.add_edge(here + 1, here, HeavyWeight::new(weight));
+            }
+            if row + 1 < SIDE {
+                graph.add_edge(here, here + SIDE, HeavyWeight::new(weight));
+                graph.add_edge(here + SIDE, here, HeavyWeight::new(weight));
+            }
+        }
+    }
+    let dijkstra = graph.dijkstra();
+    let start = graph.vertex(&0).unwrap();
+    let end = graph.vertex(&(SIDE * SIDE - 1)).unwrap();
+
+    CLONES.store(0, atomic::Ordering::Relaxed);
+    let (route, weight) = dijkstra.find_shorted_path(vec![start], vec![end]);
+    let clones = CLONES.load(atomic::Ordering::Relaxed);
+
+    assert_eq!(*route[0].id(), 0);
+    assert_eq!(*route[route.len() - 1].id(), SIDE * SIDE - 1);
+    assert!(weight.total >= 2 * (SIDE as u64 - 1));
+    // Filling the weight vector, one per settled vertex, and the result. Nothing is
+    // cloned for the heap.
+    assert!(clones <= 2 * SIDE * SIDE + 1, "{} clones", clones);
+}
+
+#[test]
+fn unvisited_vertex_order_test() {
+    let mut heap = BinaryHeap::new();
+    for &(index, weight, hops) in &[(0, 5, 1), (1, 2, 3), (2, 5, 0), (3, 9, 0), (4, 2, 1)] {
+        heap.push(UnvisitedVertex {
+            index,
+            weight: weight as u32,
+            hops,
+        });
+    }
+
+    let order: Vec<_> = iter::from_fn(|| heap.pop())
+        .map(|entry| (entry.index, entry.weight))
+        .collect();
+    assert_eq!(order, vec![(4, 2), (1, 2), (2, 5), (0, 5), (3, 9)]);
+}
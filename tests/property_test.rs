@@ -0,0 +1,122 @@
+extern crate subway;
+
+use subway::dijkstra::*;
+use subway::graph::*;
+use subway::weight::FloatWeight;
+
+const CASES: usize = 200;
+
+// Xorshift, so that every run checks the same graphs.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn random_edges(rng: &mut Rng) -> (usize, Vec<(usize, usize, f64)>) {
+    let vertex_count = 1 + rng.below(10);
+    let edge_count = rng.below(vertex_count * 3);
+    let edges = (0..edge_count)
+        .map(|_| {
+            let from = rng.below(vertex_count);
+            let to = rng.below(vertex_count);
+            (from, to, rng.below(20) as f64)
+        })
+        .collect();
+    (vertex_count, edges)
+}
+
+// Bellman-Ford relaxation over the plain edge list.
+fn reference_distances(
+    vertex_count: usize,
+    edges: &[(usize, usize, f64)],
+    start: usize,
+) -> Vec<Option<f64>> {
+    let mut distances = vec![None; vertex_count];
+    distances[start] = Some(0.0);
+    for _ in 0..vertex_count {
+        for &(from, to, weight) in edges {
+            if let Some(distance) = distances[from] {
+                if distances[to].is_none_or(|current| distance + weight < current) {
+                    distances[to] = Some(distance + weight);
+                }
+            }
+        }
+    }
+    distances
+}
+
+#[test]
+fn random_graph_property_test() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..CASES {
+        let (vertex_count, edges) = random_edges(&mut rng);
+        let mut graph = Graph::new();
+        for id in 0..vertex_count {
+            graph.add_vertex(id);
+        }
+        for &(from, to, weight) in &edges {
+            graph.add_edge(from, to, FloatWeight::new(weight).unwrap());
+        }
+
+        let dijkstra = graph.dijkstra();
+        let vertices = graph.vertices();
+
+        for start in 0..vertex_count {
+            let expected = reference_distances(vertex_count, &edges, start);
+
+            for end in 0..vertex_count {
+                let distance = dijkstra
+                    .shortest_distance(&vertices[start], &vertices[end])
+                    .map(|weight| weight.value());
+                assert_eq!(distance, expected[end], "{:?} {} -> {}", edges, start, end);
+
+                if expected[end].is_none() {
+                    assert!(dijkstra
+                        .shortest_path(&vertices[start], &vertices[end])
+                        .is_none());
+                    continue;
+                }
+
+                let (route, weight) =
+                    dijkstra.find_shorted_path(vec![&vertices[start]], vec![&vertices[end]]);
+                assert_eq!(Some(weight.value()), expected[end]);
+                assert_eq!(*route[0].id(), start);
+                assert_eq!(*route[route.len() - 1].id(), end);
+
+                let route_weight: f64 = route
+                    .windows(2)
+                    .map(|pair| {
+                        dijkstra
+                            .edges_between(pair[0], pair[1])
+                            .iter()
+                            .map(|edge| edge.get_weight().value())
+                            .fold(f64::INFINITY, f64::min)
+                    })
+                    .sum();
+                assert_eq!(route_weight, weight.value());
+            }
+
+            for &(from, to, weight) in &edges {
+                if let Some(distance) = expected[from] {
+                    assert!(expected[to].unwrap() <= distance + weight);
+                }
+            }
+        }
+
+        assert_eq!(
+            dijkstra.find_shortest_path_limited(vec![], vec![&vertices[0]], CASES),
+            Err(Error::NoPath)
+        );
+    }
+}
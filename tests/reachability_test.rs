@@ -0,0 +1,42 @@
+extern crate subway;
+
+use subway::reachability::*;
+
+// The sample graph's structure, with no weights at all.
+fn sample_neighbors(v: &&'static str) -> Vec<&'static str> {
+    match *v {
+        "S" => vec!["B", "C", "D"],
+        "C" => vec!["D"],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn bfs_reachable_test() {
+    assert_eq!(
+        bfs_reachable(vec!["S"], sample_neighbors),
+        vec!["S", "B", "C", "D"]
+    );
+    assert_eq!(bfs_reachable(vec!["C"], sample_neighbors), vec!["C", "D"]);
+    assert_eq!(
+        bfs_reachable(vec!["B", "C"], sample_neighbors),
+        vec!["B", "C", "D"]
+    );
+}
+
+#[test]
+fn bfs_shortest_hops_test() {
+    assert_eq!(
+        bfs_shortest_hops("S", "D", sample_neighbors),
+        Some((vec!["S", "D"], 1))
+    );
+    assert_eq!(
+        bfs_shortest_hops("C", "D", sample_neighbors),
+        Some((vec!["C", "D"], 1))
+    );
+    assert_eq!(
+        bfs_shortest_hops("S", "S", sample_neighbors),
+        Some((vec!["S"], 0))
+    );
+    assert_eq!(bfs_shortest_hops("D", "S", sample_neighbors), None);
+}
@@ -0,0 +1,420 @@
+extern crate subway;
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+use subway::dijkstra::*;
+use subway::weight::FloatWeight;
+
+struct TransitEdge<'a> {
+    to: &'a TransitVertex<'a>,
+    weight: FloatWeight,
+    line: &'static str,
+    departure: Option<FloatWeight>,
+    headway: Option<FloatWeight>,
+}
+
+impl<'a> Edge<'a, TransitVertex<'a>, FloatWeight> for TransitEdge<'a> {
+    fn get_to(&'a self) -> &'a TransitVertex<'a> {
+        self.to
+    }
+
+    fn get_weight(&self) -> &FloatWeight {
+        &self.weight
+    }
+}
+
+impl<'a> LineEdge<'a, TransitVertex<'a>, FloatWeight> for TransitEdge<'a> {
+    type Line = &'static str;
+    fn line(&self) -> &&'static str {
+        &self.line
+    }
+}
+
+// Lines double as modes for the multimodal router.
+impl<'a> ModeEdge<'a, TransitVertex<'a>, FloatWeight> for TransitEdge<'a> {
+    type Mode = &'static str;
+    fn mode(&self) -> &&'static str {
+        &self.line
+    }
+}
+
+impl<'a> ScheduledEdge<'a, TransitVertex<'a>, FloatWeight> for TransitEdge<'a> {
+    fn departure(&self) -> Option<&FloatWeight> {
+        self.departure.as_ref()
+    }
+}
+
+impl<'a> HeadwayEdge<'a, TransitVertex<'a>, FloatWeight> for TransitEdge<'a> {
+    fn headway(&self) -> Option<&FloatWeight> {
+        self.headway.as_ref()
+    }
+}
+
+struct TransitVertex<'a> {
+    name: &'static str,
+    edges: Vec<TransitEdge<'a>>,
+}
+
+impl<'a> Hash for TransitVertex<'a> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl<'a> PartialEq for TransitVertex<'a> {
+    fn eq(&self, other: &TransitVertex) -> bool {
+        self.name.eq(other.name)
+    }
+}
+
+impl<'a> Eq for TransitVertex<'a> {}
+
+impl<'a> PartialOrd for TransitVertex<'a> {
+    fn partial_cmp(&self, other: &TransitVertex) -> Option<Ordering> {
+        Some(Ord::cmp(self, other))
+    }
+}
+
+impl<'a> Ord for TransitVertex<'a> {
+    fn cmp(&self, other: &TransitVertex) -> Ordering {
+        self.name.cmp(other.name)
+    }
+}
+
+impl<'a> Vertex<'a, TransitEdge<'a>, FloatWeight> for TransitVertex<'a> {
+    type Edges = std::slice::Iter<'a, TransitEdge<'a>>;
+    fn edges(&'a self) -> Self::Edges {
+        self.edges.iter()
+    }
+}
+
+impl<'a> fmt::Debug for TransitVertex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<'a> TransitVertex<'a> {
+    fn new(name: &'static str) -> Self {
+        TransitVertex {
+            name,
+            edges: vec![],
+        }
+    }
+
+    fn add_edge(&mut self, to: &'a TransitVertex<'a>, weight: f64, line: &'static str) {
+        self.edges.push(TransitEdge {
+            to,
+            weight: minutes(weight),
+            line,
+            departure: None,
+            headway: None,
+        });
+    }
+
+    fn add_scheduled_edge(
+        &mut self,
+        to: &'a TransitVertex<'a>,
+        weight: f64,
+        line: &'static str,
+        departure: f64,
+    ) {
+        self.edges.push(TransitEdge {
+            to,
+            weight: minutes(weight),
+            line,
+            departure: Some(minutes(departure)),
+            headway: None,
+        });
+    }
+
+    fn add_frequent_edge(
+        &mut self,
+        to: &'a TransitVertex<'a>,
+        weight: f64,
+        line: &'static str,
+        headway: f64,
+    ) {
+        self.edges.push(TransitEdge {
+            to,
+            weight: minutes(weight),
+            line,
+            departure: None,
+            headway: Some(minutes(headway)),
+        });
+    }
+}
+
+fn minutes(value: f64) -> FloatWeight {
+    FloatWeight::new(value).unwrap()
+}
+
+fn names(route: &[&TransitVertex]) -> Vec<&'static str> {
+    route.iter().map(|v| v.name).collect()
+}
+
+#[test]
+fn transfer_penalty_test() {
+    let d = TransitVertex::new("D");
+    let mut x = TransitVertex::new("X");
+    let mut y = TransitVertex::new("Y");
+    let mut s = TransitVertex::new("S");
+
+    x.add_edge(&d, 1.0, "Blue");
+    y.add_edge(&d, 2.0, "Red");
+    s.add_edge(&x, 1.0, "Red");
+    s.add_edge(&y, 2.0, "Red");
+
+    let dijkstra = Dijkstra::new(vec![&s, &x, &y, &d]);
+
+    let (route, weight, transfers) = dijkstra
+        .find_transit_path(vec![&s], vec![&d], &minutes(5.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "Y", "D"]);
+    assert_eq!(weight.value(), 4.0);
+    assert_eq!(transfers, 0);
+
+    let (route, weight, transfers) = dijkstra
+        .find_transit_path(vec![&s], vec![&d], &minutes(1.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "X", "D"]);
+    assert_eq!(weight.value(), 3.0);
+    assert_eq!(transfers, 1);
+}
+
+#[test]
+fn mode_change_cost_test() {
+    let d = TransitVertex::new("D");
+    let mut x = TransitVertex::new("X");
+    let mut y = TransitVertex::new("Y");
+    let mut s = TransitVertex::new("S");
+
+    x.add_edge(&d, 1.0, "subway");
+    y.add_edge(&d, 3.0, "bus");
+    s.add_edge(&x, 1.0, "walk");
+    s.add_edge(&y, 3.0, "bus");
+
+    let dijkstra = Dijkstra::new(vec![&s, &x, &y, &d]);
+
+    let (route, weight, segments) = dijkstra
+        .find_multimodal_path(vec![&s], vec![&d], |_, _| minutes(1.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "X", "D"]);
+    assert_eq!(weight.value(), 3.0);
+    let segments: Vec<_> = segments
+        .iter()
+        .map(|&(&mode, ref vertices)| (mode, names(vertices)))
+        .collect();
+    assert_eq!(
+        segments,
+        vec![("walk", vec!["S", "X"]), ("subway", vec!["X", "D"])]
+    );
+
+    let (route, weight, segments) = dijkstra
+        .find_multimodal_path(vec![&s], vec![&d], |_, _| minutes(10.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "Y", "D"]);
+    assert_eq!(weight.value(), 6.0);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(*segments[0].0, "bus");
+    assert_eq!(names(&segments[0].1), vec!["S", "Y", "D"]);
+}
+
+#[test]
+fn max_wait_test() {
+    let d = TransitVertex::new("D");
+    let mut x = TransitVertex::new("X");
+    let mut y = TransitVertex::new("Y");
+    let mut s = TransitVertex::new("S");
+
+    // The train from X arrives first but leaves 3 minutes after the walk gets there; the
+    // one from Y arrives later but leaves 2 minutes after.
+    x.add_scheduled_edge(&d, 2.0, "Red", 4.0);
+    y.add_scheduled_edge(&d, 4.0, "Blue", 5.0);
+    s.add_edge(&x, 1.0, "walk");
+    s.add_edge(&y, 3.0, "walk");
+
+    let dijkstra = Dijkstra::new(vec![&s, &x, &y, &d]);
+
+    let (route, arrival) = dijkstra
+        .find_shortest_path_with_max_wait(vec![&s], vec![&d], &minutes(10.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "X", "D"]);
+    assert_eq!(arrival.value(), 6.0);
+
+    let (route, arrival) = dijkstra
+        .find_shortest_path_with_max_wait(vec![&s], vec![&d], &minutes(2.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "Y", "D"]);
+    assert_eq!(arrival.value(), 9.0);
+
+    assert!(dijkstra
+        .find_shortest_path_with_max_wait(vec![&s], vec![&d], &minutes(1.0))
+        .is_none());
+}
+
+#[test]
+fn later_arrival_max_wait_test() {
+    let d = TransitVertex::new("D");
+    let mut x = TransitVertex::new("X");
+    let mut a = TransitVertex::new("A");
+    let mut s = TransitVertex::new("S");
+
+    // Walking straight to X arrives 4 minutes before the train, the detour through A
+    // only 1 minute before.
+    x.add_scheduled_edge(&d, 2.0, "Red", 5.0);
+    a.add_edge(&x, 2.0, "walk");
+    s.add_edge(&x, 1.0, "walk");
+    s.add_edge(&a, 2.0, "walk");
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &x, &d]);
+
+    let (route, arrival) = dijkstra
+        .find_shortest_path_with_max_wait(vec![&s], vec![&d], &minutes(2.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "A", "X", "D"]);
+    assert_eq!(arrival.value(), 7.0);
+
+    let (route, _) = dijkstra
+        .find_shortest_path_with_max_wait(vec![&s], vec![&d], &minutes(5.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "X", "D"]);
+}
+
+#[test]
+fn time_windows_test() {
+    let b = TransitVertex::new("B");
+    let mut a = TransitVertex::new("A");
+    let mut s = TransitVertex::new("S");
+
+    a.add_scheduled_edge(&b, 3.0, "Red", 9.0);
+    a.add_edge(&b, 6.0, "walk");
+    s.add_edge(&a, 5.0, "walk");
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b]);
+    let window = |v, opens, closes| (v, minutes(opens), minutes(closes));
+
+    // Arriving at A by 5 catches the train at 9.
+    let (route, arrival) = dijkstra
+        .find_itinerary_with_windows(&s, &[window(&a, 0.0, 10.0), window(&b, 0.0, 12.0)])
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "A", "B"]);
+    assert_eq!(arrival.value(), 11.0);
+
+    // Staying at A until 10 misses the train, and walking on arrives too late for B.
+    assert!(dijkstra
+        .find_itinerary_with_windows(&s, &[window(&a, 10.0, 12.0), window(&b, 0.0, 15.0)])
+        .is_none());
+    let (_, arrival) = dijkstra
+        .find_itinerary_with_windows(&s, &[window(&a, 10.0, 12.0), window(&b, 0.0, 16.0)])
+        .unwrap();
+    assert_eq!(arrival.value(), 16.0);
+
+    assert!(dijkstra
+        .find_itinerary_with_windows(&s, &[window(&a, 0.0, 4.0), window(&b, 0.0, 20.0)])
+        .is_none());
+}
+
+#[test]
+fn walking_budget_test() {
+    let d = TransitVertex::new("D");
+    let mut x = TransitVertex::new("X");
+    let mut s = TransitVertex::new("S");
+
+    x.add_edge(&d, 12.0, "Red");
+    s.add_edge(&d, 10.0, "walk");
+    s.add_edge(&x, 2.0, "walk");
+
+    let dijkstra = Dijkstra::new(vec![&s, &x, &d]);
+    let walking = |edge: &TransitEdge| {
+        if edge.line == "walk" {
+            edge.weight
+        } else {
+            minutes(0.0)
+        }
+    };
+
+    let (route, weight, walked) = dijkstra
+        .find_shortest_path_with_resource(vec![&s], vec![&d], walking, &minutes(20.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "D"]);
+    assert_eq!((weight.value(), walked.value()), (10.0, 10.0));
+
+    let (route, weight, walked) = dijkstra
+        .find_shortest_path_with_resource(vec![&s], vec![&d], walking, &minutes(5.0))
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "X", "D"]);
+    assert_eq!((weight.value(), walked.value()), (14.0, 2.0));
+
+    assert!(dijkstra
+        .find_shortest_path_with_resource(vec![&s], vec![&d], walking, &minutes(1.0))
+        .is_none());
+}
+
+#[test]
+fn line_graph_test() {
+    let d = TransitVertex::new("D");
+    let mut x = TransitVertex::new("X");
+    let mut y = TransitVertex::new("Y");
+    let mut s = TransitVertex::new("S");
+
+    x.add_edge(&d, 1.0, "Blue");
+    y.add_edge(&d, 2.0, "Red");
+    s.add_edge(&x, 1.0, "Red");
+    s.add_edge(&y, 2.0, "Red");
+
+    let dijkstra = Dijkstra::new(vec![&s, &x, &y, &d]);
+    let index = |v| dijkstra.index_of(v).unwrap();
+
+    for &penalty in &[5.0, 1.0] {
+        let line_graph = dijkstra.to_line_graph(&minutes(penalty));
+        let departure = line_graph.vertex(&LineNode::Departure(index(&s))).unwrap();
+        let arrival = line_graph.vertex(&LineNode::Arrival(index(&d))).unwrap();
+        let (line_route, line_weight) = line_graph
+            .dijkstra()
+            .shortest_path(departure, arrival)
+            .unwrap();
+
+        let vertices = dijkstra.vertices();
+        let line_route: Vec<_> = line_route
+            .iter()
+            .filter_map(|node| match *node.id() {
+                LineNode::Departure(i) => Some(vertices[i]),
+                LineNode::Edge(id) => Some(vertices[id.to]),
+                LineNode::Arrival(_) => None,
+            })
+            .collect();
+
+        let (route, weight, _) = dijkstra
+            .find_transit_path(vec![&s], vec![&d], &minutes(penalty))
+            .unwrap();
+        assert_eq!(names(&line_route), names(&route));
+        assert_eq!(line_weight, weight);
+    }
+}
+
+#[test]
+fn headway_test() {
+    let d = TransitVertex::new("D");
+    let mut x = TransitVertex::new("X");
+    let mut s = TransitVertex::new("S");
+
+    // The express rides 2 minutes shorter but runs every 30 minutes, the local every 4.
+    x.add_frequent_edge(&d, 6.0, "Local", 4.0);
+    s.add_frequent_edge(&d, 10.0, "Express", 30.0);
+    s.add_frequent_edge(&x, 6.0, "Local", 4.0);
+
+    let dijkstra = Dijkstra::new(vec![&s, &x, &d]);
+    let half = |headway: &FloatWeight| minutes(headway.value() / 2.0);
+
+    let (route, expected) = dijkstra.shortest_path(&s, &d).unwrap();
+    assert_eq!((names(&route), expected.value()), (vec!["S", "D"], 10.0));
+
+    // Staying on the local waits only once.
+    let (route, expected) = dijkstra
+        .shortest_path_matching_schedule_frequency(&s, &d, half)
+        .unwrap();
+    assert_eq!(names(&route), vec!["S", "X", "D"]);
+    assert_eq!(expected.value(), 14.0);
+}
@@ -0,0 +1,558 @@
+extern crate subway;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64;
+use std::fmt;
+use std::hash::Hash;
+use std::iter;
+use std::panic::{self, AssertUnwindSafe};
+use subway::dijkstra::*;
+use subway::weight::*;
+
+struct FloatEdge<'a, W> {
+    to: &'a FloatVertex<'a, W>,
+    weight: W,
+}
+
+impl<'a, W> Edge<'a, FloatVertex<'a, W>, W> for FloatEdge<'a, W>
+where
+    W: Weight + 'a,
+{
+    fn get_to(&'a self) -> &'a FloatVertex<'a, W> {
+        self.to
+    }
+
+    fn get_weight(&self) -> &W {
+        &self.weight
+    }
+}
+
+struct FloatVertex<'a, W> {
+    name: &'static str,
+    edges: Vec<FloatEdge<'a, W>>,
+}
+
+impl<'a, W> Hash for FloatVertex<'a, W> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl<'a, W> PartialEq for FloatVertex<'a, W> {
+    fn eq(&self, other: &FloatVertex<W>) -> bool {
+        self.name.eq(other.name)
+    }
+}
+
+impl<'a, W> Eq for FloatVertex<'a, W> {}
+
+impl<'a, W> PartialOrd for FloatVertex<'a, W> {
+    fn partial_cmp(&self, other: &FloatVertex<W>) -> Option<Ordering> {
+        Some(Ord::cmp(self, other))
+    }
+}
+
+impl<'a, W> Ord for FloatVertex<'a, W> {
+    fn cmp(&self, other: &FloatVertex<W>) -> Ordering {
+        self.name.cmp(other.name)
+    }
+}
+
+impl<'a, W> Vertex<'a, FloatEdge<'a, W>, W> for FloatVertex<'a, W>
+where
+    W: Weight + 'a,
+{
+    type Edges = std::slice::Iter<'a, FloatEdge<'a, W>>;
+    fn edges(&'a self) -> Self::Edges {
+        self.edges.iter()
+    }
+}
+
+impl<'a, W> fmt::Debug for FloatVertex<'a, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<'a, W> FloatVertex<'a, W> {
+    fn new(name: &'static str) -> Self {
+        FloatVertex {
+            name,
+            edges: vec![],
+        }
+    }
+
+    fn add_edge(&mut self, to: &'a FloatVertex<'a, W>, weight: W) {
+        self.edges.push(FloatEdge { to, weight });
+    }
+}
+
+fn float(value: f64) -> FloatWeight {
+    FloatWeight::new(value).unwrap()
+}
+
+#[test]
+fn float_weight_test() {
+    assert!(FloatWeight::new(f64::NAN).is_none());
+    assert!(float(f64::INFINITY)
+        .add(&float(f64::NEG_INFINITY))
+        .is_infinity());
+
+    let d = FloatVertex::new("D");
+    let mut c = FloatVertex::new("C");
+    let b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+
+    c.add_edge(&d, float(1.25));
+    s.add_edge(&b, float(0.5));
+    s.add_edge(&c, float(0.25));
+    s.add_edge(&d, float(2.0));
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight.value(), 1.5);
+}
+
+#[test]
+fn plain_float_weight_test() {
+    let d = FloatVertex::new("D");
+    let mut c = FloatVertex::new("C");
+    let b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+
+    c.add_edge(&d, 1.25);
+    s.add_edge(&b, 0.5);
+    s.add_edge(&c, 0.25);
+    s.add_edge(&d, f64::NAN);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight, 1.5);
+    assert!(Weight::add(&f64::INFINITY, &f64::NEG_INFINITY).is_infinity());
+}
+
+#[test]
+fn float_histogram_test() {
+    let far = FloatVertex::new("Far");
+    let b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+    let mut t = FloatVertex::new("T");
+    s.add_edge(&b, float(0.5));
+    t.add_edge(&far, float(1e300));
+
+    let dijkstra = Dijkstra::new(vec![&s, &b]);
+    assert_eq!(dijkstra.distance_histogram(&s, &float(1.0)), Ok(vec![2]));
+    assert_eq!(
+        dijkstra.distance_histogram(&s, &float(0.0)),
+        Err(Error::InvalidBucketSize)
+    );
+    assert_eq!(
+        dijkstra.distance_histogram(&s, &float(-1.0)),
+        Err(Error::InvalidBucketSize)
+    );
+
+    // The bucket index saturates at `usize::MAX`, one short of the buckets needed.
+    let dijkstra = Dijkstra::new(vec![&t, &far]);
+    assert_eq!(
+        dijkstra.distance_histogram(&t, &float(1.0)),
+        Err(Error::CapacityOverflow)
+    );
+}
+
+#[test]
+fn huge_histogram_test() {
+    let far = FloatVertex::new("Far");
+    let mut s = FloatVertex::new("S");
+    s.add_edge(&far, float(1e17));
+
+    let dijkstra = Dijkstra::new(vec![&s, &far]);
+    assert_eq!(
+        dijkstra.distance_histogram(&s, &float(1.0)),
+        Err(Error::CapacityOverflow)
+    );
+}
+
+#[test]
+fn float_shortest_path_ordering_test() {
+    let d = FloatVertex::new("D");
+    let mut c = FloatVertex::new("C");
+    let b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+
+    c.add_edge(&d, 1.25);
+    s.add_edge(&b, 0.5);
+    s.add_edge(&c, 0.25);
+    s.add_edge(&d, 2.0);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    let mut paths: BinaryHeap<ShortestPath<_, f64>> = vec![&b, &d, &c]
+        .into_iter()
+        .map(|end| dijkstra.find_shorted_path(vec![&s], vec![end]).into())
+        .collect();
+    paths.push(ShortestPath {
+        route: vec![&s, &d],
+        weight: 1.5,
+    });
+
+    let order: Vec<_> = iter::from_fn(|| paths.pop())
+        .map(|path| (path.route.last().unwrap().name, path.weight, path.hops()))
+        .collect();
+    assert_eq!(
+        order,
+        vec![("D", 1.5, 2), ("D", 1.5, 1), ("B", 0.5, 1), ("C", 0.25, 1)]
+    );
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RawFloat(f64);
+
+impl Eq for RawFloat {}
+
+impl PartialOrd for RawFloat {
+    fn partial_cmp(&self, other: &RawFloat) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RawFloat {
+    fn cmp(&self, other: &RawFloat) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Weight for RawFloat {
+    fn add(&self, other: &Self) -> Self {
+        RawFloat(self.0 + other.0)
+    }
+
+    fn zero() -> Self {
+        RawFloat(0.0)
+    }
+
+    fn one() -> Self {
+        RawFloat(1.0)
+    }
+
+    fn infinity() -> Self {
+        RawFloat(f64::INFINITY)
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.0 == f64::INFINITY
+    }
+}
+
+#[test]
+#[should_panic(expected = "non-total weight ordering")]
+fn raw_float_nan_test() {
+    let b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+
+    s.add_edge(&b, RawFloat(f64::NAN));
+
+    let dijkstra = Dijkstra::new(vec![&s, &b]);
+    dijkstra.find_shorted_path(vec![&s], vec![&b]);
+}
+
+#[test]
+fn scaled_weight_test() {
+    fn route_of<W: Weight>(weight: impl Fn(f64) -> W) -> (Vec<&'static str>, W) {
+        let d = FloatVertex::new("D");
+        let mut c = FloatVertex::new("C");
+        let b = FloatVertex::new("B");
+        let mut s = FloatVertex::new("S");
+
+        c.add_edge(&d, weight(12.0));
+        s.add_edge(&b, weight(24.0));
+        s.add_edge(&c, weight(3.0));
+        s.add_edge(&d, weight(20.0));
+
+        let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+        let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+        (route.iter().map(|v| v.name).collect(), weight)
+    }
+
+    // Meters to minutes at 4 km/h.
+    let per_meter = 60.0 / 4000.0;
+    let (route, weight) = route_of(float);
+    let (scaled_route, scaled_weight) =
+        route_of(|w| ScaledWeight::new(&float(w * 1000.0), per_meter).unwrap());
+
+    assert_eq!(route, vec!["S", "C", "D"]);
+    assert_eq!(scaled_route, route);
+    assert_eq!(weight.value(), 15.0);
+    assert_eq!(scaled_weight.value().value(), 225.0);
+
+    let (scaled_route, scaled_weight) = route_of(|w| ScaledWeight::new(&(w as u32), 0.25).unwrap());
+    assert_eq!(scaled_route, route);
+    // 3 and 12 round to 1 and 3 apiece.
+    assert_eq!(*scaled_weight.value(), 4);
+
+    assert!(ScaledWeight::new(&float(1.0), -1.0).is_none());
+    assert!(ScaledWeight::new(&float(1.0), f64::NAN).is_none());
+    let infinite = ScaledWeight::new(&FloatWeight::infinity(), 0.0).unwrap();
+    assert!(infinite.is_infinity());
+    assert!(ScaledWeight::new(&u8::MAX, 0.5).unwrap().is_infinity());
+    assert!(ScaledWeight::new(&200u8, 2.0).unwrap().is_infinity());
+
+    // Forwarded rather than left at their defaults.
+    let negative_zero = ScaledWeight::new(&-0.0, 1.0).unwrap();
+    assert!(negative_zero
+        .total_cmp(&ScaledWeight::new(&0.0, 1.0).unwrap())
+        .is_lt());
+    let max = ScaledWeight::new(&saturating(200), 2.0).unwrap();
+    assert!(max.is_max() && !max.is_infinity());
+    assert_eq!(
+        ScaledWeight::new(&7u32, 1.5).unwrap().scale(2),
+        ScaledWeight::new(&22u32, 1.0).unwrap()
+    );
+}
+
+#[test]
+fn raw_float_nan_every_search_test() {
+    fn panics_on_nan(search: impl FnOnce()) -> bool {
+        match panic::catch_unwind(AssertUnwindSafe(search)) {
+            Ok(()) => false,
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .is_some_and(|message| message.contains("non-total weight ordering")),
+        }
+    }
+
+    let b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+    s.add_edge(&b, RawFloat(f64::NAN));
+    let dijkstra = Dijkstra::new(vec![&s, &b]);
+
+    assert!(panics_on_nan(|| {
+        dijkstra.find_shortest_path_astar(vec![&s], vec![&b], |_| RawFloat(0.0), None);
+    }));
+    assert!(panics_on_nan(|| {
+        dijkstra.find_shortest_path_with_resource(vec![&s], vec![&b], |_| 1u32, &5);
+    }));
+    assert!(panics_on_nan(|| {
+        let options = SearchOptions {
+            max_hops: Some(3),
+            ..SearchOptions::default()
+        };
+        dijkstra.search(vec![&s], vec![&b], &options).ok();
+    }));
+    assert!(panics_on_nan(|| {
+        implicit(0u32, |&n| vec![(n + 1, RawFloat(f64::NAN))], |&n| n == 1);
+    }));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SaturatingWeight {
+    infinite: bool,
+    value: u8,
+}
+
+fn saturating(value: u8) -> SaturatingWeight {
+    SaturatingWeight {
+        infinite: false,
+        value,
+    }
+}
+
+impl Weight for SaturatingWeight {
+    fn add(&self, other: &Self) -> Self {
+        if self.infinite || other.infinite {
+            Self::infinity()
+        } else {
+            saturating(self.value.saturating_add(other.value))
+        }
+    }
+
+    fn zero() -> Self {
+        saturating(0)
+    }
+
+    fn one() -> Self {
+        saturating(1)
+    }
+
+    fn infinity() -> Self {
+        SaturatingWeight {
+            infinite: true,
+            value: 0,
+        }
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.infinite
+    }
+
+    fn is_max(&self) -> bool {
+        self.infinite || self.value == u8::MAX
+    }
+}
+
+impl Scale for SaturatingWeight {
+    fn scale_by(&self, factor: f64) -> Self {
+        if self.infinite {
+            return Self::infinity();
+        }
+        saturating((self.value as f64 * factor).min(u8::MAX as f64) as u8)
+    }
+}
+
+#[test]
+fn saturated_budget_test() {
+    let b = FloatVertex::new("B");
+    let mut a = FloatVertex::new("A");
+    let c = FloatVertex::new("C");
+    let mut s = FloatVertex::new("S");
+
+    a.add_edge(&b, saturating(100));
+    s.add_edge(&a, saturating(200));
+    s.add_edge(&c, saturating(250));
+
+    let dijkstra = Dijkstra::new(vec![&s, &a, &b, &c]);
+    assert_eq!(
+        dijkstra.shortest_distance(&s, &b),
+        Some(saturating(u8::MAX))
+    );
+
+    let within: Vec<_> = dijkstra
+        .reachable_within(vec![&s], &saturating(u8::MAX))
+        .into_iter()
+        .map(|(v, _)| v.name)
+        .collect();
+    assert_eq!(within, vec!["S", "A", "C"]);
+
+    let options = SearchOptions {
+        budget: Some(saturating(u8::MAX)),
+        ..SearchOptions::default()
+    };
+    assert_eq!(
+        dijkstra.search(vec![&s], vec![&b], &options),
+        Err(Error::NoPath)
+    );
+    let (route, weight) = dijkstra.search(vec![&s], vec![&c], &options).unwrap();
+    assert_eq!(route, vec![&s, &c]);
+    assert_eq!(weight, saturating(250));
+}
+
+#[test]
+fn one_weight_test() {
+    fn check<W: Weight + fmt::Debug>() {
+        assert_eq!(W::zero().add(&W::one()), W::one());
+        assert!(W::zero() < W::one());
+        assert!(!W::one().is_infinity());
+    }
+
+    check::<FloatWeight>();
+    check::<ScaledWeight<FloatWeight>>();
+    check::<u8>();
+    check::<u32>();
+    check::<u64>();
+    check::<usize>();
+
+    assert_eq!(u8::MAX.add(&1), u8::infinity());
+    assert_eq!(*ScaledWeight::<u32>::one().value(), 1);
+}
+
+// Only the standard traits and `Bounded`, no `Weight` impl of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct Meters(u32);
+
+impl std::ops::Add for Meters {
+    type Output = Meters;
+    fn add(self, other: Meters) -> Meters {
+        Meters(self.0 + other.0)
+    }
+}
+
+impl From<u8> for Meters {
+    fn from(value: u8) -> Meters {
+        Meters(value.into())
+    }
+}
+
+impl Bounded for Meters {
+    fn max_value() -> Meters {
+        Meters(u32::MAX)
+    }
+}
+
+#[test]
+fn ops_weight_test() {
+    let meters = |value| OpsWeight(Meters(value));
+
+    let d = FloatVertex::new("D");
+    let mut c = FloatVertex::new("C");
+    let b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+
+    c.add_edge(&d, meters(12));
+    s.add_edge(&b, meters(24));
+    s.add_edge(&c, meters(3));
+    s.add_edge(&d, meters(20));
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    let (route, weight) = dijkstra.find_shorted_path(vec![&s], vec![&d]);
+    assert_eq!(route, vec![&s, &c, &d]);
+    assert_eq!(weight, meters(15));
+
+    assert_eq!(OpsWeight::<Meters>::one(), meters(1));
+    assert!(OpsWeight::<Meters>::infinity()
+        .add(&meters(1))
+        .is_infinity());
+}
+
+#[test]
+fn widened_weight_test() {
+    let half = usize::MAX / 2;
+
+    let d = FloatVertex::new("D");
+    let mut c = FloatVertex::new("C");
+    let mut b = FloatVertex::new("B");
+    let mut s = FloatVertex::new("S");
+
+    c.add_edge(&d, half);
+    b.add_edge(&c, half);
+    s.add_edge(&b, 3);
+
+    let dijkstra = Dijkstra::new(vec![&s, &b, &c, &d]);
+    assert_eq!(dijkstra.shortest_path(&s, &d), None);
+
+    let (route, weight) = dijkstra
+        .find_shortest_path_widened(vec![&s], vec![&d])
+        .unwrap();
+    assert_eq!(route, vec![&s, &b, &c, &d]);
+    assert_eq!(weight, 2 * half as u128 + 3);
+    assert!(usize::narrow(&weight).is_infinity());
+    assert_eq!(usize::narrow(&12u64.widen()), 12);
+}
+
+#[test]
+fn scale_test() {
+    fn check<W: Weight + fmt::Debug>(w: W) {
+        assert_eq!(w.scale(3), w.add(&w).add(&w));
+        assert_eq!(w.scale(1), w);
+        assert_eq!(w.scale(0), W::zero());
+        assert!(W::infinity().scale(0).is_infinity());
+        assert!(W::infinity().scale(2).is_infinity());
+    }
+
+    check(7u8);
+    check(7u32);
+    check(7u128);
+    check(7usize);
+    check(2.5f64);
+    check(float(2.5));
+    check(saturating(7));
+    check(OpsWeight(Meters(7)));
+
+    assert!(100u8.scale(3).is_infinity());
+    assert!(u64::MAX.scale(1).is_infinity());
+    assert_eq!((u64::MAX / 2).scale(2), u64::MAX - 1);
+    assert!((u64::MAX / 2 + 1).scale(2).is_infinity());
+    assert!(f64::MAX.scale(2).is_infinity());
+}